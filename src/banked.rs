@@ -0,0 +1,199 @@
+use alloc::vec::Vec;
+
+use embedded_storage::nor_flash::{ErrorType, NorFlash, ReadNorFlash};
+use fugit::NanosDurationU64;
+
+use crate::{FlashError, FlashStats, FlashTimings, SimulatedNorFlash, blocking::check_bounds};
+
+/// Several independent banks (dies) behind one address space.
+///
+/// Addresses are striped across the banks in units of `stripe` bytes:
+/// stripe `i` lives in bank `i % N`. Every access is split at stripe
+/// boundaries and forwarded to the banks, which keep their own stats, wear
+/// and failure models. Because banks work independently, accesses to
+/// different banks can overlap; [`parallel_time`](Self::parallel_time)
+/// estimates the duration under that assumption.
+///
+/// ```
+/// use embedded_storage::nor_flash::NorFlash;
+/// use embedded_storage_sim::{BankedFlash, FlashTimings, SimulatedNorFlash, SpiType};
+/// use fugit::{MillisDurationU64, RateExtU64};
+///
+/// let banks: Vec<SimulatedNorFlash> = (0..2).map(|_| SimulatedNorFlash::new(4 * 4096)).collect();
+/// let mut flash = BankedFlash::new(banks, 4096);
+/// flash.erase(0, 8 * 4096).unwrap();
+/// assert_eq!(flash.bank(0).page_erase_cycles(), [1, 1, 1, 1]);
+/// assert_eq!(flash.stats().pages_erased, 8);
+///
+/// let timings = FlashTimings::new(SpiType::QSPI, false, 50.MHz(), MillisDurationU64::millis(50), 40);
+/// assert_eq!(flash.parallel_time(&timings) * 2, timings.total_time(&flash.stats()));
+/// ```
+pub struct BankedFlash<O, const RS: usize, const WS: usize, const ES: usize> {
+    banks: Vec<SimulatedNorFlash<O, RS, WS, ES>>,
+    stripe: usize,
+}
+
+impl<O: Clone, const RS: usize, const WS: usize, const ES: usize> BankedFlash<O, RS, WS, ES> {
+    /// Combine `banks` with `stripe` bytes per stripe.
+    ///
+    /// Panics if there are no banks, the banks differ in size, `stripe` is
+    /// not a non-zero multiple of `ERASE_SIZE`, or the bank size is not a
+    /// multiple of `stripe`.
+    pub fn new(banks: Vec<SimulatedNorFlash<O, RS, WS, ES>>, stripe: usize) -> Self {
+        assert!(!banks.is_empty());
+        assert!(stripe > 0 && stripe.is_multiple_of(ES));
+        let bank_size = banks[0].size();
+        assert!(banks.iter().all(|bank| bank.size() == bank_size));
+        assert!(bank_size.is_multiple_of(stripe));
+        Self { banks, stripe }
+    }
+    pub fn banks(&self) -> &[SimulatedNorFlash<O, RS, WS, ES>] {
+        &self.banks
+    }
+    pub fn bank(&self, index: usize) -> &SimulatedNorFlash<O, RS, WS, ES> {
+        &self.banks[index]
+    }
+    pub fn bank_mut(&mut self, index: usize) -> &mut SimulatedNorFlash<O, RS, WS, ES> {
+        &mut self.banks[index]
+    }
+    /// Unwrap into the individual banks.
+    pub fn into_banks(self) -> Vec<SimulatedNorFlash<O, RS, WS, ES>> {
+        self.banks
+    }
+    /// Total capacity across all banks in bytes.
+    pub fn size(&self) -> usize {
+        self.banks.len() * self.banks[0].size()
+    }
+    /// Stats summed over all banks.
+    ///
+    /// An access that spans several stripes counts once per stripe.
+    pub fn stats(&self) -> FlashStats {
+        self.banks
+            .iter()
+            .fold(FlashStats::default(), |sum, bank| &sum + &bank.stats())
+    }
+    /// Estimated duration if accesses to different banks overlap perfectly.
+    ///
+    /// This is the time of the busiest bank, so it is a lower bound; the
+    /// serial time is `timings.total_time(&self.stats())`.
+    pub fn parallel_time(&self, timings: &FlashTimings) -> NanosDurationU64 {
+        self.banks
+            .iter()
+            .map(|bank| timings.total_time(&bank.stats()))
+            .max()
+            .unwrap_or(NanosDurationU64::from_ticks(0))
+    }
+    /// Split `offset..offset + len` at stripe boundaries into
+    /// `(bank, bank offset, position in range, length)`.
+    fn segments(&self, offset: u32, len: usize) -> Vec<(usize, u32, usize, usize)> {
+        let mut segments = Vec::new();
+        let mut position = 0;
+        while position < len {
+            let global = offset as usize + position;
+            let stripe_index = global / self.stripe;
+            let within = global % self.stripe;
+            let length = (self.stripe - within).min(len - position);
+            let local = stripe_index / self.banks.len() * self.stripe + within;
+            segments.push((
+                stripe_index % self.banks.len(),
+                local as u32,
+                position,
+                length,
+            ));
+            position += length;
+        }
+        segments
+    }
+}
+
+impl<O, const RS: usize, const WS: usize, const ES: usize> ErrorType
+    for BankedFlash<O, RS, WS, ES>
+{
+    type Error = FlashError;
+}
+
+impl<O: Clone, const RS: usize, const WS: usize, const ES: usize> ReadNorFlash
+    for BankedFlash<O, RS, WS, ES>
+{
+    const READ_SIZE: usize = RS;
+
+    fn read(&mut self, offset: u32, bytes: &mut [u8]) -> Result<(), Self::Error> {
+        check_bounds(
+            offset..offset.saturating_add(bytes.len() as u32),
+            self.size(),
+            RS,
+        )?;
+        for (bank, local, position, length) in self.segments(offset, bytes.len()) {
+            ReadNorFlash::read(
+                &mut self.banks[bank],
+                local,
+                &mut bytes[position..position + length],
+            )?;
+        }
+        Ok(())
+    }
+
+    fn capacity(&self) -> usize {
+        self.size()
+    }
+}
+
+impl<O: Clone, const RS: usize, const WS: usize, const ES: usize> NorFlash
+    for BankedFlash<O, RS, WS, ES>
+{
+    const WRITE_SIZE: usize = WS;
+    const ERASE_SIZE: usize = ES;
+
+    fn erase(&mut self, from: u32, to: u32) -> Result<(), Self::Error> {
+        check_bounds(from..to, self.size(), ES)?;
+        for (bank, local, _, length) in self.segments(from, (to - from) as usize) {
+            NorFlash::erase(&mut self.banks[bank], local, local + length as u32)?;
+        }
+        Ok(())
+    }
+
+    fn write(&mut self, offset: u32, bytes: &[u8]) -> Result<(), Self::Error> {
+        check_bounds(
+            offset..offset.saturating_add(bytes.len() as u32),
+            self.size(),
+            WS,
+        )?;
+        for (bank, local, position, length) in self.segments(offset, bytes.len()) {
+            NorFlash::write(
+                &mut self.banks[bank],
+                local,
+                &bytes[position..position + length],
+            )?;
+        }
+        Ok(())
+    }
+}
+
+impl<O: Clone, const RS: usize, const WS: usize, const ES: usize>
+    embedded_storage_async::nor_flash::ReadNorFlash for BankedFlash<O, RS, WS, ES>
+{
+    const READ_SIZE: usize = RS;
+
+    async fn read(&mut self, offset: u32, bytes: &mut [u8]) -> Result<(), Self::Error> {
+        ReadNorFlash::read(self, offset, bytes)
+    }
+
+    fn capacity(&self) -> usize {
+        self.size()
+    }
+}
+
+impl<O: Clone, const RS: usize, const WS: usize, const ES: usize>
+    embedded_storage_async::nor_flash::NorFlash for BankedFlash<O, RS, WS, ES>
+{
+    const WRITE_SIZE: usize = WS;
+    const ERASE_SIZE: usize = ES;
+
+    async fn erase(&mut self, from: u32, to: u32) -> Result<(), Self::Error> {
+        NorFlash::erase(self, from, to)
+    }
+
+    async fn write(&mut self, offset: u32, bytes: &[u8]) -> Result<(), Self::Error> {
+        NorFlash::write(self, offset, bytes)
+    }
+}
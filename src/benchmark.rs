@@ -0,0 +1,199 @@
+use alloc::{format, string::String, vec::Vec};
+
+use fugit::NanosDurationU64;
+
+use crate::{FlashStats, FlashTimings, OperationTag, SimulatedNorFlash};
+
+/// Summary statistics over a series of per-operation durations.
+#[derive(Debug, Clone)]
+pub struct RunData {
+    /// The durations in their original order.
+    pub data: Vec<NanosDurationU64>,
+    pub min: NanosDurationU64,
+    pub max: NanosDurationU64,
+    pub avg: NanosDurationU64,
+    /// Population standard deviation.
+    pub std_dev: NanosDurationU64,
+    /// Lower median.
+    pub median: NanosDurationU64,
+    pub p90: NanosDurationU64,
+    pub p99: NanosDurationU64,
+    pub p999: NanosDurationU64,
+    pub total: NanosDurationU64,
+    pub count: usize,
+}
+
+impl Default for RunData {
+    fn default() -> Self {
+        Self {
+            data: Vec::new(),
+            min: NanosDurationU64::from_ticks(0),
+            max: NanosDurationU64::from_ticks(0),
+            avg: NanosDurationU64::from_ticks(0),
+            std_dev: NanosDurationU64::from_ticks(0),
+            median: NanosDurationU64::from_ticks(0),
+            p90: NanosDurationU64::from_ticks(0),
+            p99: NanosDurationU64::from_ticks(0),
+            p999: NanosDurationU64::from_ticks(0),
+            total: NanosDurationU64::from_ticks(0),
+            count: 0,
+        }
+    }
+}
+
+impl From<&[NanosDurationU64]> for RunData {
+    fn from(data: &[NanosDurationU64]) -> Self {
+        let count = data.len();
+        if count == 0 {
+            return Self::default();
+        }
+        let mut sorted_data: Vec<_> = data.to_vec();
+        sorted_data.sort();
+        let total = sorted_data
+            .iter()
+            .fold(NanosDurationU64::from_ticks(0), |acc, &t| acc + t);
+        let avg = total / (count as u32);
+        let std_dev = {
+            let mean = avg;
+            let var = sorted_data.iter().fold(0, |acc, &t| {
+                let diff = t.ticks() as i64 - mean.ticks() as i64;
+                acc + (diff * diff) as u64
+            }) / (count as u64);
+            NanosDurationU64::from_ticks((var as f64).sqrt() as u64)
+        };
+        Self {
+            data: data.to_vec(),
+            min: sorted_data[0],
+            max: *sorted_data.last().unwrap(),
+            avg,
+            std_dev,
+            median: median(&sorted_data, NanosDurationU64::from_ticks(0)),
+            p90: sorted_data[(count as f32 * 0.9).ceil() as usize - 1],
+            p99: sorted_data[(count as f32 * 0.99).ceil() as usize - 1],
+            p999: sorted_data[(count as f32 * 0.999).ceil() as usize - 1],
+            total,
+            count,
+        }
+    }
+}
+
+fn median<T: Clone>(sorted_data: &[T], default: T) -> T {
+    let len = sorted_data.len();
+    if len == 0 {
+        default
+    } else if len % 2 == 1 {
+        sorted_data[len / 2].clone()
+    } else {
+        sorted_data[len / 2 - 1].clone() // lower median
+    }
+}
+
+/// Headless benchmark that records the [`FlashStats`] of every operation.
+///
+/// Each [`run`](Self::run) measures one operation as the difference in
+/// stats before and after it. [`summary`](Self::summary) converts them to
+/// simulated durations with the given [`FlashTimings`].
+///
+/// ```
+/// use embedded_storage::nor_flash::NorFlash;
+/// use embedded_storage_sim::{Benchmark, FlashTimings, SimulatedNorFlash, SpiType};
+/// use fugit::{MillisDurationU64, RateExtU64};
+///
+/// let timings = FlashTimings::new(SpiType::QSPI, false, 50.MHz(), MillisDurationU64::millis(50), 40);
+/// let mut flash: SimulatedNorFlash = SimulatedNorFlash::new(4096);
+/// let mut benchmark = Benchmark::new("write", timings);
+/// for i in 0..16 {
+///     benchmark.run(&mut flash, |flash| flash.write(i * 4, &[0; 4]).unwrap());
+/// }
+/// assert_eq!(benchmark.op_stats().len(), 16);
+/// assert_eq!(benchmark.summary().count, 16);
+/// assert!(benchmark.to_bencher().starts_with("test write ... bench:"));
+/// ```
+#[derive(Clone, Debug)]
+pub struct Benchmark {
+    name: String,
+    timings: FlashTimings,
+    op_stats: Vec<FlashStats>,
+}
+
+impl Benchmark {
+    /// Create an empty benchmark that converts stats with `timings`.
+    pub fn new(name: impl Into<String>, timings: FlashTimings) -> Self {
+        Self {
+            name: name.into(),
+            timings,
+            op_stats: Vec::new(),
+        }
+    }
+    /// Run one operation and record the stats it added.
+    pub fn run<O: Clone, const RS: usize, const WS: usize, const ES: usize, R>(
+        &mut self,
+        flash: &mut SimulatedNorFlash<O, RS, WS, ES>,
+        op: impl FnOnce(&mut SimulatedNorFlash<O, RS, WS, ES>) -> R,
+    ) -> R {
+        let before = flash.stats();
+        let result = op(flash);
+        self.op_stats.push(&flash.stats() - &before);
+        result
+    }
+    /// Like [`run`](Self::run), but tags the operation with `tag`.
+    ///
+    /// The flash collects per-tag stats in
+    /// [`stats_by_operation`](SimulatedNorFlash::stats_by_operation) and
+    /// snapshots name the last tag, for any user-defined [`OperationTag`]:
+    ///
+    /// ```
+    /// use embedded_storage::nor_flash::NorFlash;
+    /// use embedded_storage_sim::{Benchmark, FlashTimings, SimulatedNorFlash, SpiType};
+    /// use fugit::{MillisDurationU64, RateExtU64};
+    ///
+    /// let timings = FlashTimings::new(SpiType::QSPI, false, 50.MHz(), MillisDurationU64::millis(50), 40);
+    /// let mut flash: SimulatedNorFlash<String> = SimulatedNorFlash::new(2 * 4096);
+    /// let mut benchmark = Benchmark::new("mixed", timings);
+    /// benchmark.run_operation(&mut flash, "write".to_string(), |flash| flash.write(0, &[0; 8]));
+    /// benchmark.run_operation(&mut flash, "erase".to_string(), |flash| flash.erase(0, 4096));
+    /// assert_eq!(benchmark.op_stats().len(), 2);
+    /// assert_eq!(flash.stats_by_operation()["erase"].pages_erased, 1);
+    /// ```
+    pub fn run_operation<O: OperationTag, const RS: usize, const WS: usize, const ES: usize, R>(
+        &mut self,
+        flash: &mut SimulatedNorFlash<O, RS, WS, ES>,
+        tag: O,
+        op: impl FnOnce(&mut SimulatedNorFlash<O, RS, WS, ES>) -> R,
+    ) -> R {
+        self.run(flash, |flash| op(&mut flash.operation(tag)))
+    }
+    /// Name used by [`to_bencher`](Self::to_bencher).
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+    /// Stats of every recorded operation, in order.
+    pub fn op_stats(&self) -> &[FlashStats] {
+        &self.op_stats
+    }
+    /// Simulated duration of every recorded operation, in order.
+    pub fn op_times(&self) -> Vec<NanosDurationU64> {
+        self.op_stats
+            .iter()
+            .map(|stats| self.timings.total_time(stats))
+            .collect()
+    }
+    /// Summary statistics over [`op_times`](Self::op_times).
+    pub fn summary(&self) -> RunData {
+        self.op_times().as_slice().into()
+    }
+    /// Format the result like `cargo bench`, which Criterion and other
+    /// tools can consume with their `bencher` output format.
+    ///
+    /// Reports the median simulated time per operation, with the range
+    /// between the fastest and slowest operation as the deviation.
+    pub fn to_bencher(&self) -> String {
+        let summary = self.summary();
+        format!(
+            "test {} ... bench: {} ns/iter (+/- {})",
+            self.name,
+            summary.median.ticks(),
+            (summary.max - summary.min).ticks()
+        )
+    }
+}
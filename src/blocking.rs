@@ -1,14 +1,703 @@
-use std::convert::Infallible;
+use crate::{
+    AccessKind, ErasedPage, FailureEvent, FailureKind, FlashError, SimulatedNorFlash, Transaction,
+    TransactionLogLevel,
+};
 
-use crate::{SimulatedNorFlash, Transaction, TransactionLogLevel};
+use alloc::{vec, vec::Vec};
+use core::ops::Range;
+use rand::Rng;
 
 use embedded_storage::nor_flash::{ErrorType, MultiwriteNorFlash, NorFlash, ReadNorFlash};
-use rand::Rng as _;
 
 impl<O, const RS: usize, const WS: usize, const ES: usize> ErrorType
     for SimulatedNorFlash<O, RS, WS, ES>
 {
-    type Error = Infallible;
+    type Error = FlashError;
+}
+
+impl<O: Clone, const RS: usize, const WS: usize, const ES: usize> SimulatedNorFlash<O, RS, WS, ES> {
+    /// Read from the ring buffer region `base..base + len`, wrapping at its end.
+    ///
+    /// `offset` is relative to `base` and taken modulo `len`. A read that
+    /// crosses the end of the region is split into two regular reads, so
+    /// `base`, `len` and `offset` must satisfy the usual `READ_SIZE`
+    /// alignment. This is a convenience for ring-buffer experiments: the
+    /// [`ReadNorFlash`] trait methods themselves never wrap.
+    ///
+    /// An empty `bytes` is a no-op. A region that extends past `u32::MAX`
+    /// fails with [`FlashError::OutOfBounds`], like any other access past
+    /// the end of the flash.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `bytes` is longer than the region, including any non-empty
+    /// `bytes` with `len == 0`.
+    ///
+    /// ```
+    /// use embedded_storage_sim::{FlashError, SimulatedNorFlash};
+    ///
+    /// let mut flash: SimulatedNorFlash = SimulatedNorFlash::new(4096);
+    /// flash.write_wrapping(0, 16, 12, &[1, 2, 3, 4, 5, 6, 7, 8]).unwrap();
+    /// let mut buf = [0; 8];
+    /// flash.read_wrapping(0, 16, 28, &mut buf).unwrap();
+    /// assert_eq!(buf, [1, 2, 3, 4, 5, 6, 7, 8]);
+    ///
+    /// flash.read_wrapping(0, 0, 0, &mut []).unwrap();
+    /// assert_eq!(
+    ///     flash.read_wrapping(u32::MAX - 3, 8, 4, &mut buf),
+    ///     Err(FlashError::OutOfBounds { offset: u32::MAX - 3 })
+    /// );
+    /// ```
+    pub fn read_wrapping(
+        &mut self,
+        base: u32,
+        len: u32,
+        offset: u32,
+        bytes: &mut [u8],
+    ) -> Result<(), <Self as ErrorType>::Error> {
+        if bytes.is_empty() {
+            return Ok(());
+        }
+        assert!(bytes.len() <= len as usize);
+        let offset = offset % len;
+        let Some(start) = base.checked_add(offset) else {
+            let reason = FlashError::OutOfBounds { offset: base };
+            return Err(self.reject(AccessKind::Read, base, reason));
+        };
+        let (head, tail) = bytes.split_at_mut(bytes.len().min((len - offset) as usize));
+        ReadNorFlash::read(self, start, head)?;
+        if !tail.is_empty() {
+            ReadNorFlash::read(self, base, tail)?;
+        }
+        Ok(())
+    }
+    /// Write to the ring buffer region `base..base + len`, wrapping at its end.
+    ///
+    /// See [`read_wrapping`](Self::read_wrapping), including its panics and
+    /// errors. The [`NorFlash`] trait methods themselves never wrap.
+    pub fn write_wrapping(
+        &mut self,
+        base: u32,
+        len: u32,
+        offset: u32,
+        bytes: &[u8],
+    ) -> Result<(), <Self as ErrorType>::Error> {
+        if bytes.is_empty() {
+            return Ok(());
+        }
+        assert!(bytes.len() <= len as usize);
+        let offset = offset % len;
+        let Some(start) = base.checked_add(offset) else {
+            let reason = FlashError::OutOfBounds { offset: base };
+            return Err(self.reject(AccessKind::Write, base, reason));
+        };
+        let (head, tail) = bytes.split_at(bytes.len().min((len - offset) as usize));
+        NorFlash::write(self, start, head)?;
+        if !tail.is_empty() {
+            NorFlash::write(self, base, tail)?;
+        }
+        Ok(())
+    }
+    /// Erase the whole flash with a single command.
+    ///
+    /// Equivalent to `erase(0, size)`: every page's cycle count is
+    /// incremented and the failure model runs per page, exactly as for a
+    /// ranged erase.
+    ///
+    /// ```
+    /// use embedded_storage::nor_flash::NorFlash;
+    /// use embedded_storage_sim::SimulatedNorFlash;
+    ///
+    /// let mut all: SimulatedNorFlash = SimulatedNorFlash::new(16 * 4096);
+    /// let mut ranged: SimulatedNorFlash = SimulatedNorFlash::new(16 * 4096);
+    /// all.erase_all().unwrap();
+    /// ranged.erase(0, 16 * 4096).unwrap();
+    /// assert_eq!(all.page_erase_cycles(), ranged.page_erase_cycles());
+    /// assert_eq!(all.pages_erased(), ranged.pages_erased());
+    /// assert_eq!(all.total_accesses(), ranged.total_accesses());
+    /// ```
+    pub fn erase_all(&mut self) -> Result<(), <Self as ErrorType>::Error> {
+        NorFlash::erase(self, 0, self.size() as u32)
+    }
+    /// [`NorFlash::erase`] that returns the number of bytes and pages erased.
+    ///
+    /// On success these are `to - from` and `(to - from) / ERASE_SIZE`,
+    /// minus any blank pages skipped with
+    /// [`with_skip_erased_pages`](crate::SimulatedNorFlashBuilder::with_skip_erased_pages).
+    /// An interrupted erase returns [`FlashError::EraseFailed`] instead.
+    pub fn erase_counted(
+        &mut self,
+        from: u32,
+        to: u32,
+    ) -> Result<(usize, usize), <Self as ErrorType>::Error> {
+        if let Err(reason) = self.check_access(from..to, AccessKind::Erase) {
+            return Err(self.reject(AccessKind::Erase, from, reason));
+        }
+
+        let mut pages = 0;
+        let mut completed_pages = 0;
+        let mut failed = false;
+        for page_index in (from as usize..to as usize)
+            .step_by(Self::ERASE_SIZE)
+            .map(|page| page / Self::ERASE_SIZE)
+        {
+            if self.skip_erased_pages && self.is_page_blank(page_index) {
+                completed_pages += 1;
+                continue;
+            }
+            if self.erase_failure_rate > 0.0 && self.rng.random::<f32>() < self.erase_failure_rate {
+                failed = true;
+                break;
+            }
+            completed_pages += 1;
+            pages += 1;
+            self.page_last_erased_op[page_index] = Some(self.total_operations);
+            if !self.ram_semantics {
+                self.wear_page(page_index);
+            }
+        }
+        // only the completed pages are erased
+        let end = from + (completed_pages * Self::ERASE_SIZE) as u32;
+        let range = from as usize..end as usize;
+        if self.log_level != TransactionLogLevel::None && (!failed || !range.is_empty()) {
+            self.record(Transaction::erase(
+                self.log_level,
+                from,
+                end,
+                self.data.get(range.clone()).unwrap_or_default(),
+                self.current_operation.clone(),
+            ));
+        }
+        if !self.ram_semantics && !self.metadata_only {
+            self.data[range.clone()].fill(0xff);
+            self.update_sector_crcs(range.clone());
+        }
+        self.invalidate_read_cache(range.clone());
+        self.check_low_space(false);
+        if !self.ram_semantics
+            && let Some(initialized) = self.initialized.get_mut(range.clone())
+        {
+            initialized.fill(false);
+        }
+        if let Some(programmed) = self.programmed.get_mut(range.clone()) {
+            programmed.fill(false);
+        }
+        if pages != 0 {
+            self.erased_pages = self.erased_pages.saturating_add(pages);
+            self.erase_accesses = self.erase_accesses.saturating_add(1);
+            self.calibration.on_access(AccessKind::Erase, pages);
+        }
+        if failed {
+            return Err(self.reject(
+                AccessKind::Erase,
+                from,
+                FlashError::EraseFailed { completed_pages },
+            ));
+        }
+        Ok((pages * Self::ERASE_SIZE, pages))
+    }
+    /// Erase the sub-sectors in `from..to`.
+    ///
+    /// Requires a sub-sector size set with
+    /// [`with_subsector_size`](crate::SimulatedNorFlashBuilder::with_subsector_size);
+    /// `from` and `to` must be aligned to it. Each sub-sector keeps its own
+    /// cycle count, and a page's count in
+    /// [`page_erase_cycles`](Self::page_erase_cycles) is the highest of its
+    /// sub-sectors. The failure model runs on the erased sub-sectors only.
+    /// Sub-sector erases are counted in [`FlashStats::subsectors_erased`](crate::FlashStats::subsectors_erased)
+    /// and timed with
+    /// [`with_subsector_erase_time`](crate::FlashTimingsBuilder::with_subsector_erase_time).
+    ///
+    /// ```
+    /// use embedded_storage_sim::{SimulatedNorFlash, SimulatedNorFlashBuilder};
+    ///
+    /// let mut flash: SimulatedNorFlash<(), 1, 1, 65536> = SimulatedNorFlashBuilder::new(2 * 65536)
+    ///     .with_subsector_size(4096)
+    ///     .build();
+    /// flash.erase_subsector(4096, 3 * 4096).unwrap();
+    /// flash.erase_subsector(4096, 2 * 4096).unwrap();
+    /// assert_eq!(&flash.subsector_erase_cycles()[..4], [0, 2, 1, 0]);
+    /// assert_eq!(flash.page_erase_cycles(), [2, 0]);
+    /// assert_eq!(flash.stats().subsectors_erased, 3);
+    /// assert_eq!(flash.pages_erased(), 0);
+    /// ```
+    pub fn erase_subsector(
+        &mut self,
+        from: u32,
+        to: u32,
+    ) -> Result<(), <Self as ErrorType>::Error> {
+        let subsector_size = self.subsector_size;
+        assert!(subsector_size != 0, "no sub-sector size configured");
+        if let Err(reason) = self.check_access_aligned(from..to, subsector_size) {
+            return Err(self.reject(AccessKind::Erase, from, reason));
+        }
+
+        let range = from as usize..to as usize;
+        for offset in range.clone().step_by(subsector_size) {
+            self.page_last_erased_op[offset / Self::ERASE_SIZE] = Some(self.total_operations);
+        }
+        if !self.ram_semantics {
+            for subsector in range.clone().step_by(subsector_size) {
+                self.wear_subsector(subsector / subsector_size);
+            }
+        }
+        if self.log_level != TransactionLogLevel::None {
+            self.record(Transaction::erase(
+                self.log_level,
+                from,
+                to,
+                self.data.get(range.clone()).unwrap_or_default(),
+                self.current_operation.clone(),
+            ));
+        }
+        if !self.ram_semantics && !self.metadata_only {
+            self.data[range.clone()].fill(0xff);
+            self.update_sector_crcs(range.clone());
+        }
+        self.invalidate_read_cache(range.clone());
+        self.check_low_space(false);
+        if !self.ram_semantics
+            && let Some(initialized) = self.initialized.get_mut(range.clone())
+        {
+            initialized.fill(false);
+        }
+        if let Some(programmed) = self.programmed.get_mut(range.clone()) {
+            programmed.fill(false);
+        }
+        if !range.is_empty() {
+            self.erased_subsectors = self
+                .erased_subsectors
+                .saturating_add(range.len() / subsector_size);
+            self.erase_accesses = self.erase_accesses.saturating_add(1);
+        }
+        Ok(())
+    }
+    /// Overwrite `bytes` at `offset` via read-modify-write of the containing pages.
+    ///
+    /// Every page touched by the range is read, erased with a single
+    /// command and programmed again with the change applied, so `offset`
+    /// and the length need no alignment. This is costly: updating a single
+    /// byte reads and erases a whole `ERASE_SIZE` page and programs every
+    /// `WRITE_SIZE` unit of it that isn't blank, and all of it shows up in
+    /// the stats and wear. The worst case, a page without blank units,
+    /// matches [`PlannedOp::Rewrite`](crate::PlannedOp::Rewrite) in
+    /// [`estimate`](Self::estimate).
+    ///
+    /// ```
+    /// use embedded_storage::nor_flash::{NorFlash, ReadNorFlash};
+    /// use embedded_storage_sim::SimulatedNorFlash;
+    ///
+    /// let mut flash: SimulatedNorFlash = SimulatedNorFlash::new(2 * 4096);
+    /// flash.write(0, b"hello world").unwrap();
+    /// flash.rmw(6, b"flash").unwrap();
+    /// let mut data = [0; 11];
+    /// flash.read(0, &mut data).unwrap();
+    /// assert_eq!(&data, b"hello flash");
+    /// assert_eq!((flash.bytes_read(), flash.pages_erased()), (4096 + 11, 1));
+    /// assert_eq!(flash.bytes_written(), 11 + 11);
+    /// ```
+    ///
+    /// The pre-image is the stored contents, never the
+    /// [poison pattern](crate::SimulatedNorFlashBuilder::with_uninitialized_poison)
+    /// of unwritten bytes, and blank units stay unprogrammed, so they can
+    /// still be written with
+    /// [rewrite detection](crate::SimulatedNorFlashBuilder::with_rewrite_detection):
+    ///
+    /// ```
+    /// use embedded_storage::nor_flash::{NorFlash, ReadNorFlash};
+    /// use embedded_storage_sim::{FlashError, SimulatedNorFlash, SimulatedNorFlashBuilder};
+    ///
+    /// let mut flash: SimulatedNorFlash = SimulatedNorFlashBuilder::new(4096)
+    ///     .with_uninitialized_poison(&[0xde, 0xad])
+    ///     .with_rewrite_detection(true)
+    ///     .build();
+    /// flash.rmw(2, &[0x42]).unwrap();
+    /// assert_eq!(&flash.data()[..4], [0xff, 0xff, 0x42, 0xff]);
+    /// flash.write(8, &[0x43]).unwrap();
+    /// assert_eq!(
+    ///     flash.write(2, &[0x40]),
+    ///     Err(FlashError::RewriteWithoutErase { offset: 2 })
+    /// );
+    ///
+    /// assert_eq!(flash.rmw(u32::MAX, &[0; 2]), Err(FlashError::OutOfBounds { offset: u32::MAX }));
+    /// assert_eq!(flash.rmw(4095, &[0; 2]), Err(FlashError::OutOfBounds { offset: 4096 }));
+    /// assert_eq!(flash.pages_erased(), 1);
+    /// ```
+    pub fn rmw(&mut self, offset: u32, bytes: &[u8]) -> Result<(), <Self as ErrorType>::Error> {
+        let Some(end) = offset.checked_add(bytes.len() as u32) else {
+            let reason = FlashError::OutOfBounds { offset };
+            return Err(self.reject(AccessKind::Write, offset, reason));
+        };
+        if let Err(reason) = self.check_access_aligned(offset..end, 1) {
+            return Err(self.reject(AccessKind::Write, offset, reason));
+        }
+        if bytes.is_empty() {
+            return Ok(());
+        }
+        let from = Self::align_down_erase(offset);
+        let to = match Self::align_up_erase(end) {
+            Ok(to) => to,
+            Err(reason) => return Err(self.reject(AccessKind::Write, offset, reason)),
+        };
+        let mut pages = vec![0; (to - from) as usize];
+        ReadNorFlash::read(self, from, &mut pages)?;
+        if self.uninitialized_poison.is_some() {
+            for (i, byte) in pages.iter_mut().enumerate() {
+                if !self.initialized[from as usize + i] {
+                    *byte = self.effective_byte(from + i as u32);
+                }
+            }
+        }
+        let start = (offset - from) as usize;
+        pages[start..start + bytes.len()].copy_from_slice(bytes);
+        NorFlash::erase(self, from, to)?;
+        let units = pages.len() / WS;
+        let blank = |unit: usize| pages[unit * WS..(unit + 1) * WS].iter().all(|&b| b == 0xff);
+        let mut unit = 0;
+        while unit < units {
+            if blank(unit) {
+                unit += 1;
+                continue;
+            }
+            let first = unit;
+            while unit < units && !blank(unit) {
+                unit += 1;
+            }
+            NorFlash::write(
+                self,
+                from + (first * WS) as u32,
+                &pages[first * WS..unit * WS],
+            )?;
+        }
+        Ok(())
+    }
+    /// [`NorFlash::write`] that returns the number of bytes written.
+    ///
+    /// On success this is always `bytes.len()`. With
+    /// [`with_write_verify`](crate::SimulatedNorFlashBuilder::with_write_verify)
+    /// the write is read back afterwards, see [`FlashError::VerifyFailed`].
+    pub fn write_counted(
+        &mut self,
+        offset: u32,
+        bytes: &[u8],
+    ) -> Result<usize, <Self as ErrorType>::Error> {
+        let end = offset.saturating_add(bytes.len() as u32);
+        if let Err(reason) = self.check_access(offset..end, AccessKind::Write) {
+            return Err(self.reject(AccessKind::Write, offset, reason));
+        }
+
+        let range = offset as usize..(offset as usize + bytes.len());
+        if !self.ram_semantics
+            && !self.metadata_only
+            && let Some(i) = bytes
+                .iter()
+                .zip(&self.data[range.clone()])
+                .position(|(new, old)| new & !old != 0)
+        {
+            self.writes_setting_bits = self.writes_setting_bits.saturating_add(1);
+            if self.strict_writes {
+                return Err(self.reject(
+                    AccessKind::Write,
+                    offset,
+                    FlashError::WriteToNonErased {
+                        offset: offset + i as u32,
+                    },
+                ));
+            }
+        }
+        if let Some(i) = self
+            .programmed
+            .get(range.clone())
+            .and_then(|programmed| programmed.iter().position(|&programmed| programmed))
+        {
+            return Err(self.reject(
+                AccessKind::Write,
+                offset,
+                FlashError::RewriteWithoutErase {
+                    offset: offset + i as u32,
+                },
+            ));
+        }
+        // what a verify read should return, without stuck bits or failed programs
+        let expected: Vec<u8> = if !self.write_verify || self.metadata_only {
+            Vec::new()
+        } else if self.ram_semantics {
+            bytes.to_vec()
+        } else {
+            bytes
+                .iter()
+                .zip(&self.data[range.clone()])
+                .map(|(new, old)| new & old)
+                .collect()
+        };
+        let program_failures = if self.ram_semantics || self.metadata_only {
+            Vec::new()
+        } else {
+            self.failed_programs(range.clone(), bytes)
+        };
+        // the stored contents before the write, for invertible logs
+        let before_write: Vec<u8> = match self.log_level {
+            TransactionLogLevel::WriteBeforeAfter | TransactionLogLevel::Full => {
+                self.data.get(range.clone()).unwrap_or_default().to_vec()
+            }
+            _ => Vec::new(),
+        };
+        for (i, byte) in self
+            .data
+            .get_mut(range.clone())
+            .unwrap_or_default()
+            .iter_mut()
+            .enumerate()
+        {
+            if self.ram_semantics {
+                *byte = bytes[i];
+            } else {
+                *byte &= bytes[i];
+            }
+        }
+        for &(offset, mask) in &program_failures {
+            self.data[offset] |= mask;
+        }
+        self.update_sector_crcs(range.clone());
+        self.invalidate_read_cache(range.clone());
+        self.check_low_space(true);
+        self.program_failures = self.program_failures.saturating_add(program_failures.len());
+        if let Some(initialized) = self.initialized.get_mut(range.clone()) {
+            initialized.fill(true);
+        }
+        if let Some(programmed) = self.programmed.get_mut(range.clone()) {
+            programmed.fill(true);
+        }
+        if self.log_level != TransactionLogLevel::None {
+            // the contents as they read back, only captured with `before_write`
+            let after_write: Vec<u8> = match self.log_level {
+                TransactionLogLevel::WriteBeforeAfter | TransactionLogLevel::Full
+                    if self.metadata_only =>
+                {
+                    bytes.to_vec()
+                }
+                TransactionLogLevel::WriteBeforeAfter | TransactionLogLevel::Full => range
+                    .clone()
+                    .map(|i| self.effective_byte(i as u32))
+                    .collect(),
+                _ => Vec::new(),
+            };
+            self.record(Transaction::write(
+                self.log_level,
+                offset,
+                bytes,
+                &before_write,
+                &after_write,
+                self.current_operation.clone(),
+            ));
+        }
+        if !bytes.is_empty() {
+            self.written = self.written.saturating_add(bytes.len());
+            let end = offset + bytes.len() as u32;
+            self.written_extent = Some(match self.written_extent.take() {
+                Some(extent) => extent.start.min(offset)..extent.end.max(end),
+                None => offset..end,
+            });
+            self.program_units_written = self
+                .program_units_written
+                .saturating_add(self.program_units(offset, bytes.len()));
+            self.write_accesses = self
+                .write_accesses
+                .saturating_add(self.accesses(offset, bytes.len()));
+            self.calibration.on_access(AccessKind::Write, bytes.len());
+        }
+        if self.write_verify && !bytes.is_empty() {
+            let mut readback = vec![0; bytes.len()];
+            ReadNorFlash::read(self, offset, &mut readback)?;
+            if let Some(i) = expected
+                .iter()
+                .zip(&readback)
+                .position(|(expected, actual)| expected != actual)
+            {
+                let reason = FlashError::VerifyFailed {
+                    offset: offset + i as u32,
+                };
+                return Err(self.reject(AccessKind::Write, offset, reason));
+            }
+        }
+        Ok(bytes.len())
+    }
+    /// Ask the failure model which bits the write of `bytes` to `range`
+    /// fails to clear, as `(offset, mask)` pairs.
+    fn failed_programs(&mut self, range: Range<usize>, bytes: &[u8]) -> Vec<(usize, u8)> {
+        let mut failures = Vec::new();
+        let mut start = range.start;
+        while start < range.end {
+            let page_index = start / Self::ERASE_SIZE;
+            let page = page_index * Self::ERASE_SIZE;
+            let end = range.end.min(page + Self::ERASE_SIZE);
+            let programmed_page = ErasedPage {
+                index: page_index,
+                size: Self::ERASE_SIZE,
+                cycles: self.page_cycles[page_index],
+                endurance: self.page_endurance(page_index),
+            };
+            let page_range = start - page..end - page;
+            for failure in
+                self.failure_model
+                    .on_program(&programmed_page, page_range, &mut self.rng)
+            {
+                let offset = page + failure.offset;
+                let mask = 1 << failure.bit;
+                // only bits this write actually clears can fail
+                if range.contains(&offset)
+                    && self.data[offset] & mask != 0
+                    && bytes[offset - range.start] & mask == 0
+                    && !failures.contains(&(offset, mask))
+                {
+                    failures.push((offset, mask));
+                }
+            }
+            start = end;
+        }
+        failures
+    }
+    /// Whether page `page_index` is stored and reads as all `0xFF`.
+    fn is_page_blank(&self, page_index: usize) -> bool {
+        let page = page_index * Self::ERASE_SIZE;
+        !self.metadata_only
+            && (page..page + Self::ERASE_SIZE).all(|i| self.effective_byte(i as u32) == 0xff)
+    }
+    /// Log a rejected access unless logging is disabled, and return `reason`.
+    fn reject(&mut self, kind: AccessKind, offset: u32, reason: FlashError) -> FlashError {
+        if self.log_level != TransactionLogLevel::None {
+            self.record(Transaction::rejected(
+                kind,
+                offset,
+                reason,
+                self.current_operation.clone(),
+            ));
+        }
+        reason
+    }
+    /// Check whether an access of `kind` to `range` is allowed at all.
+    ///
+    /// This is the single place for constraints that don't depend on the
+    /// contents, checked in order: bounds, alignment to `READ_SIZE`,
+    /// `WRITE_SIZE` or `ERASE_SIZE`, and unmapped regions.
+    pub(crate) fn check_access(
+        &self,
+        range: Range<u32>,
+        kind: AccessKind,
+    ) -> Result<(), FlashError> {
+        let alignment = match kind {
+            AccessKind::Read => Self::READ_SIZE,
+            AccessKind::Write => Self::WRITE_SIZE,
+            AccessKind::Erase => Self::ERASE_SIZE,
+        };
+        self.check_access_aligned(range, alignment)
+    }
+    /// [`check_access`](Self::check_access) with an explicit `alignment`,
+    /// e.g. for sub-sector erases.
+    pub(crate) fn check_access_aligned(
+        &self,
+        range: Range<u32>,
+        alignment: usize,
+    ) -> Result<(), FlashError> {
+        check_bounds(range.clone(), self.size(), alignment)?;
+        match self
+            .unmapped_regions
+            .iter()
+            .filter(|region| region.start < range.end && range.start < region.end)
+            .map(|region| region.start.max(range.start))
+            .min()
+        {
+            Some(offset) => Err(FlashError::Unmapped { offset }),
+            None => Ok(()),
+        }
+    }
+    /// Count one erase cycle on `page_index` and apply the wear-out model.
+    fn wear_page(&mut self, page_index: usize) {
+        let cycles = self.page_cycles[page_index].saturating_add(1);
+        self.page_cycles[page_index] = cycles;
+        let subsectors = self.subsectors_of_page(page_index);
+        self.subsector_cycles[subsectors].fill(cycles);
+        self.fire_wear_threshold(page_index, cycles);
+        self.inject_defects(page_index, 0, Self::ERASE_SIZE, cycles);
+    }
+    /// Count one erase cycle on sub-sector `subsector` and apply the
+    /// wear-out model to it.
+    fn wear_subsector(&mut self, subsector: usize) {
+        let start = subsector * self.subsector_size;
+        let page_index = start / Self::ERASE_SIZE;
+        let cycles = self.subsector_cycles[subsector].saturating_add(1);
+        self.subsector_cycles[subsector] = cycles;
+        // a page is as worn as its most worn sub-sector
+        if cycles > self.page_cycles[page_index] {
+            self.page_cycles[page_index] = cycles;
+            self.fire_wear_threshold(page_index, cycles);
+        }
+        self.inject_defects(
+            page_index,
+            start % Self::ERASE_SIZE,
+            self.subsector_size,
+            cycles,
+        );
+    }
+    /// Fire the wear threshold callback if `cycles` just passed the
+    /// endurance of `page_index`.
+    fn fire_wear_threshold(&mut self, page_index: usize, cycles: u32) {
+        if cycles - 1 == self.page_endurance(page_index)
+            && let Some(callback) = self.wear_threshold_callback.as_mut()
+        {
+            callback(page_index, cycles);
+        }
+    }
+    /// Run the failure model on the erased `size` bytes at `start` within
+    /// page `page_index`.
+    fn inject_defects(&mut self, page_index: usize, start: usize, size: usize, cycles: u32) {
+        if self.metadata_only {
+            return;
+        }
+        let erased_page = ErasedPage {
+            index: page_index,
+            size,
+            cycles,
+            endurance: self.page_endurance(page_index),
+        };
+        let region = page_index * Self::ERASE_SIZE + start;
+        for defect in self.failure_model.on_erase(&erased_page, &mut self.rng) {
+            let global_offset = region + defect.offset;
+            match defect.kind {
+                FailureKind::StuckAt1 => self.stuck_at_1_bits[global_offset] |= 1 << defect.bit,
+                FailureKind::StuckAt0 => self.stuck_at_0_bits[global_offset] |= 1 << defect.bit,
+            }
+            if let Some(callback) = self.failure_callback.as_mut() {
+                callback(FailureEvent {
+                    offset: global_offset as u32,
+                    bit: defect.bit,
+                    kind: defect.kind,
+                    page_cycles: cycles,
+                });
+            }
+        }
+    }
+}
+
+/// Check that `range` lies within `size` bytes and starts and ends at a
+/// multiple of `alignment`.
+pub(crate) fn check_bounds(
+    range: Range<u32>,
+    size: usize,
+    alignment: usize,
+) -> Result<(), FlashError> {
+    if range.start > range.end || range.end as usize > size {
+        return Err(FlashError::OutOfBounds {
+            offset: range.start.max(range.end.min(size as u32)),
+        });
+    }
+    match [range.start, range.end]
+        .into_iter()
+        .find(|&offset| !(offset as usize).is_multiple_of(alignment))
+    {
+        Some(offset) => Err(FlashError::NotAligned { offset }),
+        None => Ok(()),
+    }
 }
 
 impl<O: Clone, const RS: usize, const WS: usize, const ES: usize> ReadNorFlash
@@ -17,20 +706,44 @@ impl<O: Clone, const RS: usize, const WS: usize, const ES: usize> ReadNorFlash
     const READ_SIZE: usize = RS;
 
     fn read(&mut self, offset: u32, bytes: &mut [u8]) -> Result<(), Self::Error> {
-        assert_eq!(0, offset % Self::READ_SIZE as u32);
-        assert_eq!(0, bytes.len() % Self::READ_SIZE);
-        assert!(offset as usize + bytes.len() <= self.data.len());
+        let end = offset.saturating_add(bytes.len() as u32);
+        if let Err(reason) = self.check_access(offset..end, AccessKind::Read) {
+            return Err(self.reject(AccessKind::Read, offset, reason));
+        }
 
-        bytes.copy_from_slice(&self.data[offset as usize..offset as usize + bytes.len()]);
-        for (i, byte) in bytes.iter_mut().enumerate() {
-            *byte |= self.stuck_at_1_bits[offset as usize + i];
-            *byte &= !self.stuck_at_0_bits[offset as usize + i];
+        if self.metadata_only {
+            bytes.fill(self.initial_value());
+        } else {
+            bytes.copy_from_slice(&self.data[offset as usize..offset as usize + bytes.len()]);
+            let mut corrupt = false;
+            for (i, byte) in bytes.iter_mut().enumerate() {
+                let stored = *byte;
+                *byte |= self.stuck_at_1_bits[offset as usize + i];
+                *byte &= !self.stuck_at_0_bits[offset as usize + i];
+                if *byte != stored {
+                    corrupt = true;
+                    if let Some(callback) = self.corrupt_read_callback.as_mut() {
+                        callback(offset + i as u32);
+                    }
+                }
+            }
+            if corrupt {
+                self.corrupt_reads = self.corrupt_reads.saturating_add(1);
+            }
+        }
+        if let Some(pattern) = &self.uninitialized_poison {
+            for (i, byte) in bytes.iter_mut().enumerate() {
+                let cell = offset as usize + i;
+                if !self.initialized[cell] {
+                    *byte = pattern[cell % pattern.len()];
+                }
+            }
         }
 
         match self.log_level {
             TransactionLogLevel::None | TransactionLogLevel::Minimal => {}
             _ => {
-                self.transactions.push(Transaction::read(
+                self.record(Transaction::read(
                     self.log_level,
                     offset,
                     bytes.len(),
@@ -40,13 +753,18 @@ impl<O: Clone, const RS: usize, const WS: usize, const ES: usize> ReadNorFlash
             }
         }
 
-        self.read += bytes.len();
-        self.read_accesses += 1;
+        if !bytes.is_empty() && !self.read_cache_lookup(offset, bytes.len()) {
+            self.read = self.read.saturating_add(bytes.len());
+            self.read_accesses = self
+                .read_accesses
+                .saturating_add(self.accesses(offset, bytes.len()));
+            self.calibration.on_access(AccessKind::Read, bytes.len());
+        }
         Ok(())
     }
 
     fn capacity(&self) -> usize {
-        self.data.len()
+        self.size()
     }
 }
 impl<O: Clone, const RS: usize, const WS: usize, const ES: usize> NorFlash
@@ -56,75 +774,11 @@ impl<O: Clone, const RS: usize, const WS: usize, const ES: usize> NorFlash
     const ERASE_SIZE: usize = ES;
 
     fn erase(&mut self, from: u32, to: u32) -> Result<(), Self::Error> {
-        assert_eq!(0, from % Self::ERASE_SIZE as u32);
-        assert_eq!(0, to % Self::ERASE_SIZE as u32);
-        assert!(from < to);
-        assert!((to as usize) <= self.data.len());
-
-        let range = from as usize..to as usize;
-        for page in range.clone().step_by(Self::ERASE_SIZE) {
-            let page_index = page / Self::ERASE_SIZE;
-            self.page_cycles[page_index] += 1;
-            if self.page_cycles[page_index] > self.minimum_safe_erase_cycles {
-                if (self.page_cycles[page_index] - self.minimum_safe_erase_cycles)
-                    % self.bit_failure_every_x_erases
-                    == 0
-                {
-                    // Introduce a stuck-at-1 or stuck-at-0 bit failure at a random location in the page
-                    let failure_offset = self.rng.random_range(0..Self::ERASE_SIZE);
-                    let global_offset = page + failure_offset;
-                    if self.rng.random::<bool>() {
-                        // Stuck-at-1
-                        self.stuck_at_1_bits[global_offset] |= 1 << self.rng.random_range(0..8);
-                    } else {
-                        // Stuck-at-0
-                        self.stuck_at_0_bits[global_offset] |= 1 << self.rng.random_range(0..8);
-                    }
-                }
-            }
-        }
-        if self.log_level != TransactionLogLevel::None {
-            self.transactions.push(Transaction::erase(
-                self.log_level,
-                from,
-                to,
-                &self.data[range.clone()],
-                self.current_operation.clone(),
-            ));
-        }
-        self.data[range.clone()].fill(0xff);
-        // inject stuck at 0 errors
-        for i in range.clone() {
-            self.data[i] &= !self.stuck_at_0_bits[i];
-        }
-        self.erased += (to - from) as usize;
-        self.erase_accesses += 1;
-        Ok(())
+        self.erase_counted(from, to).map(|_| ())
     }
 
     fn write(&mut self, offset: u32, bytes: &[u8]) -> Result<(), Self::Error> {
-        assert!(offset as usize + bytes.len() <= self.data.len());
-        assert_eq!(0, offset % Self::WRITE_SIZE as u32);
-        assert_eq!(0, bytes.len() % Self::WRITE_SIZE);
-
-        let range = offset as usize..(offset as usize + bytes.len());
-        for (i, byte) in self.data[range.clone()].iter_mut().enumerate() {
-            *byte &= bytes[i];
-            *byte |= self.stuck_at_1_bits[offset as usize + i];
-            *byte &= !self.stuck_at_0_bits[offset as usize + i];
-        }
-        if self.log_level != TransactionLogLevel::None {
-            self.transactions.push(Transaction::write(
-                self.log_level,
-                offset,
-                bytes,
-                &self.data[range],
-                self.current_operation.clone(),
-            ));
-        }
-        self.written += bytes.len();
-        self.write_accesses += 1;
-        Ok(())
+        self.write_counted(offset, bytes).map(|_| ())
     }
 }
 
@@ -0,0 +1,151 @@
+use alloc::collections::VecDeque;
+
+use fugit::NanosDurationU64;
+
+use crate::{AccessKind, FlashTimings, SimulatedNorFlash};
+
+/// Number of measured samples kept; older ones are dropped.
+const MEASURED_SAMPLES: usize = 1024;
+
+/// Difference between measured and estimated durations of one access kind.
+///
+/// Positive values mean the hardware was slower than the estimate.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct TimingError {
+    /// Number of measured samples.
+    pub samples: usize,
+    /// Mean of measured minus estimated time in nanoseconds.
+    pub mean_ns: i64,
+    /// Population standard deviation of the difference in nanoseconds.
+    pub std_dev_ns: u64,
+}
+
+/// Per access kind [`TimingError`], see [`SimulatedNorFlash::timing_error_report`].
+///
+/// A kind without samples is `None`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct TimingErrorReport {
+    pub read: Option<TimingError>,
+    pub write: Option<TimingError>,
+    pub erase: Option<TimingError>,
+}
+
+#[derive(Clone, Copy)]
+struct Sample {
+    kind: AccessKind,
+    /// Bytes for reads and writes, pages for erases.
+    size: usize,
+    measured: NanosDurationU64,
+}
+
+/// Measured durations paired with the size of the access they belong to.
+#[derive(Default)]
+pub(crate) struct Calibration {
+    /// Size of the most recent access per kind, indexed like [`index`].
+    last_size: [usize; 3],
+    samples: VecDeque<Sample>,
+}
+
+fn index(kind: AccessKind) -> usize {
+    match kind {
+        AccessKind::Read => 0,
+        AccessKind::Write => 1,
+        AccessKind::Erase => 2,
+    }
+}
+
+impl Calibration {
+    /// Remember the size of a completed access.
+    pub(crate) fn on_access(&mut self, kind: AccessKind, size: usize) {
+        self.last_size[index(kind)] = size;
+    }
+    fn record(&mut self, kind: AccessKind, measured: NanosDurationU64) {
+        if self.samples.len() == MEASURED_SAMPLES {
+            self.samples.pop_front();
+        }
+        self.samples.push_back(Sample {
+            kind,
+            size: self.last_size[index(kind)],
+            measured,
+        });
+    }
+    fn error(&self, kind: AccessKind, timings: &FlashTimings) -> Option<TimingError> {
+        let estimate = |size: usize| -> NanosDurationU64 {
+            match kind {
+                AccessKind::Read => timings.read_time(size, 1),
+                AccessKind::Write => timings.write_time(size, 1),
+                AccessKind::Erase => timings.erase_time_nanos(size, 1),
+            }
+        };
+        let errors = self
+            .samples
+            .iter()
+            .filter(|sample| sample.kind == kind)
+            .map(|sample| sample.measured.ticks() as i128 - estimate(sample.size).ticks() as i128);
+        let samples = errors.clone().count();
+        if samples == 0 {
+            return None;
+        }
+        // i128 sums of 1024 u64 differences can't overflow, squares saturate
+        let mean_ns = errors.clone().sum::<i128>() / samples as i128;
+        let variance = errors
+            .map(|error| (error - mean_ns).unsigned_abs().saturating_pow(2))
+            .fold(0u128, u128::saturating_add)
+            / samples as u128;
+        Some(TimingError {
+            samples,
+            mean_ns: mean_ns.clamp(i64::MIN.into(), i64::MAX.into()) as i64,
+            std_dev_ns: variance.isqrt() as u64,
+        })
+    }
+}
+
+impl<O: Clone, const RS: usize, const WS: usize, const ES: usize> SimulatedNorFlash<O, RS, WS, ES> {
+    /// Record the measured duration of the most recent access of `kind`.
+    ///
+    /// Call this right after performing the same access on real hardware.
+    /// The sample is paired with the size of the last simulated access of
+    /// that kind and compared against its estimate in
+    /// [`timing_error_report`](Self::timing_error_report). Only the latest
+    /// 1024 samples are kept.
+    pub fn record_measured(&mut self, kind: AccessKind, measured: NanosDurationU64) {
+        self.calibration.record(kind, measured);
+    }
+    /// Compare the recorded measurements with the estimates of `timings`.
+    ///
+    /// Use this to calibrate [`FlashTimings`] against traces from a real
+    /// device:
+    ///
+    /// ```
+    /// use embedded_storage::nor_flash::NorFlash;
+    /// use embedded_storage_sim::{AccessKind, FlashTimings, SimulatedNorFlash, SpiType};
+    /// use fugit::{MillisDurationU64, NanosDurationU64, RateExtU64};
+    ///
+    /// let timings = FlashTimings::new(SpiType::SPI, false, 8.MHz(), MillisDurationU64::millis(40), 0);
+    /// let mut flash: SimulatedNorFlash = SimulatedNorFlash::new(4096);
+    /// flash.write(0, &[0; 100]).unwrap();
+    /// flash.record_measured(AccessKind::Write, NanosDurationU64::micros(110));
+    /// flash.write(100, &[0; 100]).unwrap();
+    /// flash.record_measured(AccessKind::Write, NanosDurationU64::micros(130));
+    ///
+    /// let report = flash.timing_error_report(&timings);
+    /// let write = report.write.unwrap();
+    /// assert_eq!((write.samples, write.mean_ns, write.std_dev_ns), (2, 20_000, 10_000));
+    /// assert_eq!(report.erase, None);
+    ///
+    /// // errors of several seconds, e.g. from a stalled part, don't overflow
+    /// for measured in [5, 15] {
+    ///     flash.erase(0, 4096).unwrap();
+    ///     flash.record_measured(AccessKind::Erase, NanosDurationU64::secs(measured));
+    /// }
+    /// let erase = flash.timing_error_report(&timings).erase.unwrap();
+    /// assert_eq!((erase.mean_ns, erase.std_dev_ns), (9_960_000_000, 5_000_000_000));
+    /// ```
+    pub fn timing_error_report(&self, timings: &FlashTimings) -> TimingErrorReport {
+        TimingErrorReport {
+            read: self.calibration.error(AccessKind::Read, timings),
+            write: self.calibration.error(AccessKind::Write, timings),
+            erase: self.calibration.error(AccessKind::Erase, timings),
+        }
+    }
+}
@@ -0,0 +1,346 @@
+//! Compact binary encoding of the transaction log.
+//!
+//! Each transaction starts with a kind byte, followed by the operation tag
+//! as an optional string and the variant's fields. Integers are LEB128
+//! varints, byte strings are length-prefixed, and optional buffers carry a
+//! presence byte. Operation tags are stored via [`ToString`], so decoding
+//! yields `Transaction<String>`.
+
+use alloc::{
+    string::{String, ToString},
+    vec::Vec,
+};
+use std::io::{self, Read, Write};
+
+use crate::{AccessKind, FlashError, SimulatedNorFlash, Transaction};
+
+const READ: u8 = 0;
+const WRITE: u8 = 1;
+const ERASE: u8 = 2;
+const CORRUPTION: u8 = 3;
+const MARKER: u8 = 4;
+const REJECTED: u8 = 5;
+
+impl<O: Clone + ToString, const RS: usize, const WS: usize, const ES: usize>
+    SimulatedNorFlash<O, RS, WS, ES>
+{
+    /// Write the recorded transaction log to `writer` in a compact binary format.
+    ///
+    /// This is much smaller than JSON for large logs. Read it back with
+    /// [`decode_transactions`]:
+    ///
+    /// ```
+    /// use embedded_storage::nor_flash::NorFlash;
+    /// use embedded_storage_sim::{
+    ///     SimulatedNorFlash, SimulatedNorFlashBuilder, TransactionLogLevel, decode_transactions,
+    /// };
+    ///
+    /// let mut flash: SimulatedNorFlash<&str> = SimulatedNorFlashBuilder::new(4096)
+    ///     .with_logging(TransactionLogLevel::Full)
+    ///     .build();
+    /// flash.start_operation("store");
+    /// flash.erase(0, 4096).unwrap();
+    /// flash.write(16, b"data").unwrap();
+    /// flash.mark("commit");
+    ///
+    /// let mut encoded = Vec::new();
+    /// flash.encode_transactions(&mut encoded).unwrap();
+    /// let decoded = decode_transactions(encoded.as_slice()).unwrap();
+    /// assert_eq!(decoded.len(), 3);
+    /// for (original, decoded) in flash.transactions().iter().zip(&decoded) {
+    ///     assert_eq!(original.to_json(), decoded.to_json());
+    /// }
+    /// ```
+    pub fn encode_transactions<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        for transaction in self.transactions() {
+            encode_transaction(&mut writer, transaction)?;
+        }
+        Ok(())
+    }
+}
+
+/// Read a transaction log written by
+/// [`SimulatedNorFlash::encode_transactions`] until the end of `reader`.
+///
+/// Corrupt or truncated input fails with an error. Buffers only grow with
+/// the bytes actually read, so a corrupt length can't allocate more memory
+/// than the input holds:
+///
+/// ```
+/// use std::io::ErrorKind;
+///
+/// use embedded_storage_sim::decode_transactions;
+///
+/// // a marker whose label claims to be 1 TiB long
+/// let corrupt = [4, 0, 0x80, 0x80, 0x80, 0x80, 0x80, 0x20, b'a'];
+/// let error = decode_transactions(&corrupt[..]).unwrap_err();
+/// assert_eq!(error.kind(), ErrorKind::UnexpectedEof);
+/// ```
+pub fn decode_transactions<R: Read>(mut reader: R) -> io::Result<Vec<Transaction<String>>> {
+    let mut transactions = Vec::new();
+    let mut kind = [0];
+    loop {
+        match reader.read(&mut kind) {
+            Ok(0) => return Ok(transactions),
+            Ok(_) => transactions.push(decode_transaction(&mut reader, kind[0])?),
+            Err(error) if error.kind() == io::ErrorKind::Interrupted => {}
+            Err(error) => return Err(error),
+        }
+    }
+}
+
+fn encode_transaction<O: ToString>(
+    w: &mut impl Write,
+    transaction: &Transaction<O>,
+) -> io::Result<()> {
+    let kind = match transaction {
+        Transaction::Read { .. } => READ,
+        Transaction::Write { .. } => WRITE,
+        Transaction::Erase { .. } => ERASE,
+        Transaction::Corruption { .. } => CORRUPTION,
+        Transaction::Marker { .. } => MARKER,
+        Transaction::Rejected { .. } => REJECTED,
+    };
+    w.write_all(&[kind])?;
+    match transaction.operation() {
+        Some(operation) => {
+            w.write_all(&[1])?;
+            write_bytes(w, operation.to_string().as_bytes())?;
+        }
+        None => w.write_all(&[0])?,
+    }
+    match transaction {
+        Transaction::Read {
+            offset,
+            length,
+            data,
+            ..
+        } => {
+            write_varint(w, *offset as u64)?;
+            write_varint(w, *length as u64)?;
+            write_optional_bytes(w, data)?;
+        }
+        Transaction::Write {
+            offset,
+            length,
+            data,
+            before_write,
+            after_write,
+            ..
+        } => {
+            write_varint(w, *offset as u64)?;
+            write_varint(w, *length as u64)?;
+            write_optional_bytes(w, data)?;
+            write_optional_bytes(w, before_write)?;
+            write_optional_bytes(w, after_write)?;
+        }
+        Transaction::Erase { from, to, data, .. } => {
+            write_varint(w, *from as u64)?;
+            write_varint(w, *to as u64)?;
+            write_optional_bytes(w, data)?;
+        }
+        Transaction::Corruption {
+            from, to, count, ..
+        } => {
+            write_varint(w, *from as u64)?;
+            write_varint(w, *to as u64)?;
+            write_varint(w, *count as u64)?;
+        }
+        Transaction::Marker { label, .. } => write_bytes(w, label.as_bytes())?,
+        Transaction::Rejected {
+            kind,
+            offset,
+            reason,
+            ..
+        } => {
+            let kind = match kind {
+                AccessKind::Read => 0,
+                AccessKind::Write => 1,
+                AccessKind::Erase => 2,
+            };
+            w.write_all(&[kind])?;
+            write_varint(w, *offset as u64)?;
+            match reason {
+                FlashError::WriteToNonErased { offset } => {
+                    w.write_all(&[0])?;
+                    write_varint(w, *offset as u64)?;
+                }
+                FlashError::EraseFailed { completed_pages } => {
+                    w.write_all(&[1])?;
+                    write_varint(w, *completed_pages as u64)?;
+                }
+                FlashError::VerifyFailed { offset } => {
+                    w.write_all(&[2])?;
+                    write_varint(w, *offset as u64)?;
+                }
+                FlashError::RewriteWithoutErase { offset } => {
+                    w.write_all(&[3])?;
+                    write_varint(w, *offset as u64)?;
+                }
+                FlashError::Unmapped { offset } => {
+                    w.write_all(&[4])?;
+                    write_varint(w, *offset as u64)?;
+                }
+                FlashError::OutOfBounds { offset } => {
+                    w.write_all(&[5])?;
+                    write_varint(w, *offset as u64)?;
+                }
+                FlashError::NotAligned { offset } => {
+                    w.write_all(&[6])?;
+                    write_varint(w, *offset as u64)?;
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+fn decode_transaction(r: &mut impl Read, kind: u8) -> io::Result<Transaction<String>> {
+    let operation = match read_u8(r)? {
+        0 => None,
+        1 => Some(read_string(r)?),
+        _ => return Err(invalid_data("invalid operation tag")),
+    };
+    Ok(match kind {
+        READ => Transaction::Read {
+            operation,
+            offset: read_varint(r)? as u32,
+            length: read_varint(r)? as usize,
+            data: read_optional_bytes(r)?,
+        },
+        WRITE => Transaction::Write {
+            operation,
+            offset: read_varint(r)? as u32,
+            length: read_varint(r)? as usize,
+            data: read_optional_bytes(r)?,
+            before_write: read_optional_bytes(r)?,
+            after_write: read_optional_bytes(r)?,
+        },
+        ERASE => Transaction::Erase {
+            operation,
+            from: read_varint(r)? as u32,
+            to: read_varint(r)? as u32,
+            data: read_optional_bytes(r)?,
+        },
+        CORRUPTION => Transaction::Corruption {
+            operation,
+            from: read_varint(r)? as u32,
+            to: read_varint(r)? as u32,
+            count: read_varint(r)? as usize,
+        },
+        MARKER => Transaction::Marker {
+            operation,
+            label: read_string(r)?,
+        },
+        REJECTED => {
+            let kind = match read_u8(r)? {
+                0 => AccessKind::Read,
+                1 => AccessKind::Write,
+                2 => AccessKind::Erase,
+                _ => return Err(invalid_data("invalid access kind")),
+            };
+            let offset = read_varint(r)? as u32;
+            let reason = match read_u8(r)? {
+                0 => FlashError::WriteToNonErased {
+                    offset: read_varint(r)? as u32,
+                },
+                1 => FlashError::EraseFailed {
+                    completed_pages: read_varint(r)? as usize,
+                },
+                2 => FlashError::VerifyFailed {
+                    offset: read_varint(r)? as u32,
+                },
+                3 => FlashError::RewriteWithoutErase {
+                    offset: read_varint(r)? as u32,
+                },
+                4 => FlashError::Unmapped {
+                    offset: read_varint(r)? as u32,
+                },
+                5 => FlashError::OutOfBounds {
+                    offset: read_varint(r)? as u32,
+                },
+                6 => FlashError::NotAligned {
+                    offset: read_varint(r)? as u32,
+                },
+                _ => return Err(invalid_data("invalid rejection reason")),
+            };
+            Transaction::Rejected {
+                operation,
+                kind,
+                offset,
+                reason,
+            }
+        }
+        _ => return Err(invalid_data("invalid transaction kind")),
+    })
+}
+
+fn invalid_data(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+fn write_varint(w: &mut impl Write, mut value: u64) -> io::Result<()> {
+    while value >= 0x80 {
+        w.write_all(&[value as u8 | 0x80])?;
+        value >>= 7;
+    }
+    w.write_all(&[value as u8])
+}
+
+fn read_varint(r: &mut impl Read) -> io::Result<u64> {
+    let mut value = 0;
+    for shift in (0..64).step_by(7) {
+        let byte = read_u8(r)?;
+        value |= ((byte & 0x7f) as u64) << shift;
+        if byte & 0x80 == 0 {
+            return Ok(value);
+        }
+    }
+    Err(invalid_data("varint too long"))
+}
+
+fn read_u8(r: &mut impl Read) -> io::Result<u8> {
+    let mut byte = [0];
+    r.read_exact(&mut byte)?;
+    Ok(byte[0])
+}
+
+fn write_bytes(w: &mut impl Write, bytes: &[u8]) -> io::Result<()> {
+    write_varint(w, bytes.len() as u64)?;
+    w.write_all(bytes)
+}
+
+fn read_bytes(r: &mut impl Read) -> io::Result<Vec<u8>> {
+    let length = read_varint(r)?;
+    let mut bytes = Vec::new();
+    r.take(length).read_to_end(&mut bytes)?;
+    if bytes.len() as u64 != length {
+        return Err(io::Error::new(
+            io::ErrorKind::UnexpectedEof,
+            "truncated byte string",
+        ));
+    }
+    Ok(bytes)
+}
+
+fn read_string(r: &mut impl Read) -> io::Result<String> {
+    String::from_utf8(read_bytes(r)?).map_err(|_| invalid_data("invalid UTF-8"))
+}
+
+fn write_optional_bytes(w: &mut impl Write, bytes: &Option<Vec<u8>>) -> io::Result<()> {
+    match bytes {
+        Some(bytes) => {
+            w.write_all(&[1])?;
+            write_bytes(w, bytes)
+        }
+        None => w.write_all(&[0]),
+    }
+}
+
+fn read_optional_bytes(r: &mut impl Read) -> io::Result<Option<Vec<u8>>> {
+    match read_u8(r)? {
+        0 => Ok(None),
+        1 => read_bytes(r).map(Some),
+        _ => Err(invalid_data("invalid presence flag")),
+    }
+}
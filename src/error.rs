@@ -0,0 +1,86 @@
+use core::fmt::{self, Display};
+
+use embedded_storage::nor_flash::{NorFlashError, NorFlashErrorKind};
+
+/// Error returned by the [`SimulatedNorFlash`](crate::SimulatedNorFlash) storage traits.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FlashError {
+    /// A write in strict mode needed to set a bit that is currently 0.
+    ///
+    /// `offset` is the first byte that would have ended up as the AND of
+    /// its old and new value. See
+    /// [`with_strict_writes`](crate::SimulatedNorFlashBuilder::with_strict_writes).
+    WriteToNonErased { offset: u32 },
+    /// An erase was interrupted after its first `completed_pages` pages.
+    ///
+    /// See
+    /// [`with_erase_failure_rate`](crate::SimulatedNorFlashBuilder::with_erase_failure_rate).
+    EraseFailed { completed_pages: usize },
+    /// Reading back a write returned different data at `offset`, e.g.
+    /// because of a stuck bit.
+    ///
+    /// The write itself was carried out. See
+    /// [`with_write_verify`](crate::SimulatedNorFlashBuilder::with_write_verify).
+    VerifyFailed { offset: u32 },
+    /// A write hit `offset`, which was already programmed since its last erase.
+    ///
+    /// See
+    /// [`with_rewrite_detection`](crate::SimulatedNorFlashBuilder::with_rewrite_detection).
+    RewriteWithoutErase { offset: u32 },
+    /// An access touched `offset`, which lies in a hole of the memory map.
+    ///
+    /// Nothing was accessed. See
+    /// [`with_unmapped_region`](crate::SimulatedNorFlashBuilder::with_unmapped_region).
+    Unmapped { offset: u32 },
+    /// An access reached past the end of the flash, first at `offset`, or
+    /// an erase range ended before it started.
+    OutOfBounds { offset: u32 },
+    /// An access started or ended at `offset`, which is not a multiple of
+    /// the `READ_SIZE`, `WRITE_SIZE` or `ERASE_SIZE` it needs.
+    NotAligned { offset: u32 },
+}
+
+impl NorFlashError for FlashError {
+    fn kind(&self) -> NorFlashErrorKind {
+        match self {
+            FlashError::WriteToNonErased { .. }
+            | FlashError::EraseFailed { .. }
+            | FlashError::VerifyFailed { .. }
+            | FlashError::RewriteWithoutErase { .. } => NorFlashErrorKind::Other,
+            FlashError::Unmapped { .. } | FlashError::OutOfBounds { .. } => {
+                NorFlashErrorKind::OutOfBounds
+            }
+            FlashError::NotAligned { .. } => NorFlashErrorKind::NotAligned,
+        }
+    }
+}
+
+impl Display for FlashError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FlashError::WriteToNonErased { offset } => {
+                write!(f, "write to non-erased cell at 0x{offset:x}")
+            }
+            FlashError::EraseFailed { completed_pages } => {
+                write!(f, "erase failed after {completed_pages} pages")
+            }
+            FlashError::VerifyFailed { offset } => {
+                write!(f, "write verification failed at 0x{offset:x}")
+            }
+            FlashError::RewriteWithoutErase { offset } => {
+                write!(f, "rewrite without erase at 0x{offset:x}")
+            }
+            FlashError::Unmapped { offset } => {
+                write!(f, "access to unmapped address 0x{offset:x}")
+            }
+            FlashError::OutOfBounds { offset } => {
+                write!(f, "access out of bounds at 0x{offset:x}")
+            }
+            FlashError::NotAligned { offset } => {
+                write!(f, "unaligned access at 0x{offset:x}")
+            }
+        }
+    }
+}
+
+impl core::error::Error for FlashError {}
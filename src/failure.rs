@@ -0,0 +1,247 @@
+use alloc::{boxed::Box, sync::Arc, vec, vec::Vec};
+use core::ops::Range;
+
+use rand::{Rng as _, RngCore, SeedableRng, rngs::SmallRng};
+
+/// Type of a stuck bit injected by the wear-out model.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum FailureKind {
+    /// The bit always reads as 0.
+    StuckAt0,
+    /// The bit always reads as 1.
+    StuckAt1,
+}
+
+/// A stuck bit injected during an erase, as passed to
+/// [`SimulatedNorFlash::set_failure_callback`](crate::SimulatedNorFlash::set_failure_callback).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FailureEvent {
+    /// Absolute byte offset of the defect.
+    pub offset: u32,
+    /// Bit position within the byte (0 = LSB).
+    pub bit: u8,
+    /// Whether the bit is stuck at 0 or 1.
+    pub kind: FailureKind,
+    /// Erase cycle count of the page when the defect appeared.
+    pub page_cycles: u32,
+}
+
+/// A stuck bit currently present, as returned by
+/// [`SimulatedNorFlash::stuck_bits`](crate::SimulatedNorFlash::stuck_bits).
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct StuckBit {
+    /// Absolute byte offset of the defect.
+    pub offset: u32,
+    /// Bit position within the byte (0 = LSB).
+    pub bit: u8,
+    /// Whether the bit is stuck at 0 or 1.
+    pub kind: FailureKind,
+}
+
+/// A stuck bit to inject into an erased page, as returned by a [`FailureModel`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Defect {
+    /// Byte offset within the page.
+    pub offset: usize,
+    /// Bit position within the byte (0 = LSB).
+    pub bit: u8,
+    /// Whether the bit is stuck at 0 or 1.
+    pub kind: FailureKind,
+}
+
+/// A bit that fails to program to 0 during a single write, as returned by
+/// [`FailureModel::on_program`].
+///
+/// Unlike a [`Defect`] this is not permanent: the bit stays 1 after this
+/// write, but a later write can still clear it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ProgramFailure {
+    /// Byte offset within the page.
+    pub offset: usize,
+    /// Bit position within the byte (0 = LSB).
+    pub bit: u8,
+}
+
+/// Wear state of a page that was just erased, as passed to [`FailureModel::on_erase`].
+///
+/// Also describes the page being programmed in [`FailureModel::on_program`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ErasedPage {
+    /// Page index (offset / `ERASE_SIZE`).
+    pub index: usize,
+    /// Size of the erased region in bytes: `ERASE_SIZE`, or the sub-sector
+    /// size for
+    /// [`SimulatedNorFlash::erase_subsector`](crate::SimulatedNorFlash::erase_subsector).
+    /// [`Defect`] offsets are relative to the start of that region.
+    pub size: usize,
+    /// Erase cycle count, including this erase.
+    pub cycles: u32,
+    /// Rated endurance of the page, see
+    /// [`SimulatedNorFlash::page_endurance`](crate::SimulatedNorFlash::page_endurance).
+    pub endurance: u32,
+}
+
+/// Policy deciding which stuck bits appear when a page is erased.
+///
+/// Implement this to plug e.g. a Weibull endurance distribution or a
+/// temperature-dependent model into
+/// [`SimulatedNorFlashBuilder::with_failure_model`](crate::SimulatedNorFlashBuilder::with_failure_model).
+/// Draw all randomness from `rng` to keep runs reproducible under a fixed
+/// seed.
+pub trait FailureModel: Send {
+    /// Called once for every erased page, after its cycle count was incremented.
+    fn on_erase(&mut self, page: &ErasedPage, rng: &mut dyn RngCore) -> Vec<Defect>;
+    /// Called for every page a write touches, with `range` being the
+    /// written bytes within the page.
+    ///
+    /// Returned bits that the write would clear stay 1 and are counted in
+    /// [`program_failures`](crate::SimulatedNorFlash::program_failures);
+    /// all others are ignored. Programs never fail by default.
+    fn on_program(
+        &mut self,
+        page: &ErasedPage,
+        range: Range<usize>,
+        rng: &mut dyn RngCore,
+    ) -> Vec<ProgramFailure> {
+        let _ = (page, range, rng);
+        Vec::new()
+    }
+}
+
+/// Per-page multiplier for the stuck-bit failure rate.
+pub(crate) type FailureWeight = Arc<dyn Fn(usize) -> f32 + Send + Sync>;
+
+/// Builds a fresh [`FailureModel`] for every flash built from a builder.
+pub(crate) type FailureModelFactory = Arc<dyn Fn() -> Box<dyn FailureModel> + Send + Sync>;
+
+/// The default wear-out model.
+///
+/// Once a page exceeds its endurance, one random stuck-at-0 or stuck-at-1
+/// bit is injected every `bit_failure_every_x_erases` further erase cycles.
+/// Optionally, writes to such a page fail to clear a random bit, see
+/// [`with_program_failure_rate`](Self::with_program_failure_rate).
+#[derive(Clone)]
+pub struct SimpleWearModel {
+    bit_failure_every_x_erases: u32,
+    weight: Option<FailureWeight>,
+    program_failure_rate: f32,
+}
+
+impl SimpleWearModel {
+    /// Inject one stuck bit every `bit_failure_every_x_erases` cycles past the endurance.
+    pub fn new(bit_failure_every_x_erases: u32) -> Self {
+        Self {
+            bit_failure_every_x_erases,
+            weight: None,
+            program_failure_rate: 0.0,
+        }
+    }
+    /// Scale the failure rate per page, see
+    /// [`SimulatedNorFlashBuilder::with_failure_weight`](crate::SimulatedNorFlashBuilder::with_failure_weight).
+    pub fn with_weight(mut self, weight: impl Fn(usize) -> f32 + Send + Sync + 'static) -> Self {
+        self.weight = Some(Arc::new(weight));
+        self
+    }
+    pub(crate) fn with_shared_weight(mut self, weight: Option<FailureWeight>) -> Self {
+        self.weight = weight;
+        self
+    }
+    /// Let each write to a page past its endurance fail with `probability`.
+    ///
+    /// A failing write leaves one random bit within the written range at 1
+    /// if it was supposed to be cleared. Defaults to `0.0`, in which case
+    /// writes draw no randomness.
+    pub fn with_program_failure_rate(mut self, probability: f32) -> Self {
+        self.program_failure_rate = probability;
+        self
+    }
+}
+
+impl FailureModel for SimpleWearModel {
+    fn on_erase(&mut self, page: &ErasedPage, rng: &mut dyn RngCore) -> Vec<Defect> {
+        if page.cycles <= page.endurance {
+            return Vec::new();
+        }
+        let failure_interval = match &self.weight {
+            Some(weight) => {
+                // rounded to the nearest interval, `f32::round` needs std
+                (self.bit_failure_every_x_erases as f32 / weight(page.index) + 0.5) as u32
+            }
+            None => self.bit_failure_every_x_erases,
+        };
+        if !(page.cycles - page.endurance).is_multiple_of(failure_interval.max(1)) {
+            return Vec::new();
+        }
+        // Introduce a stuck-at-1 or stuck-at-0 bit failure at a random location in the page
+        let offset = rng.random_range(0..page.size);
+        let kind = if rng.random::<bool>() {
+            FailureKind::StuckAt1
+        } else {
+            FailureKind::StuckAt0
+        };
+        let bit = rng.random_range(0..8);
+        vec![Defect { offset, bit, kind }]
+    }
+
+    fn on_program(
+        &mut self,
+        page: &ErasedPage,
+        range: Range<usize>,
+        rng: &mut dyn RngCore,
+    ) -> Vec<ProgramFailure> {
+        if self.program_failure_rate <= 0.0
+            || page.cycles <= page.endurance
+            || rng.random::<f32>() >= self.program_failure_rate
+        {
+            return Vec::new();
+        }
+        let offset = rng.random_range(range);
+        let bit = rng.random_range(0..8);
+        vec![ProgramFailure { offset, bit }]
+    }
+}
+
+/// Failure injection RNG that counts its draws.
+///
+/// Every draw advances the inner generator by exactly one `next_u64`, so
+/// the state is fully described by the seed and the number of draws, and
+/// can be restored by replaying that many draws on a freshly seeded RNG.
+pub(crate) struct FailureRng {
+    rng: SmallRng,
+    draws: u64,
+}
+
+impl FailureRng {
+    pub(crate) fn new(seed: u64) -> Self {
+        Self {
+            rng: SmallRng::seed_from_u64(seed),
+            draws: 0,
+        }
+    }
+    /// Seed with `seed` and fast-forward by `draws` draws.
+    pub(crate) fn restore(seed: u64, draws: u64) -> Self {
+        let mut rng = Self::new(seed);
+        for _ in 0..draws {
+            rng.next_u64();
+        }
+        rng
+    }
+    pub(crate) fn draws(&self) -> u64 {
+        self.draws
+    }
+}
+
+impl RngCore for FailureRng {
+    fn next_u32(&mut self) -> u32 {
+        (self.next_u64() >> 32) as u32
+    }
+    fn next_u64(&mut self) -> u64 {
+        self.draws += 1;
+        self.rng.next_u64()
+    }
+    fn fill_bytes(&mut self, dst: &mut [u8]) {
+        for chunk in dst.chunks_mut(8) {
+            chunk.copy_from_slice(&self.next_u64().to_le_bytes()[..chunk.len()]);
+        }
+    }
+}
@@ -6,7 +6,10 @@ use egui_plot::{
     PlotPoints, VPlacement,
 };
 use egui_tiles::{Tabs, TileId};
-use embedded_storage_sim::{FlashSnapshot, SimulatedNorFlashBuilder, TransactionLogLevel};
+use embedded_storage_sim::{
+    FlashSnapshot, RunData, SimulatedNorFlashBuilder, TransactionLogLevel, human_readable_bytes,
+    wear_color, wear_color_scale,
+};
 use fugit::NanosDurationU64;
 use std::{
     fmt::Display,
@@ -23,6 +26,8 @@ pub struct AppState {
     pub run_data: HashMap<String, RunData>,
     pub new_snapshot: Option<FlashSnapshot>,
     pub snapshot: Option<FlashSnapshot>,
+    /// Sleep for the estimated duration of every operation.
+    pub realtime_playback: bool,
 }
 
 pub struct NorFlashApp {
@@ -68,10 +73,22 @@ impl Runner {
         app_state: Arc<Mutex<AppState>>,
         is_running: Arc<AtomicBool>,
     ) -> Result<(), ()> {
-        let flash_size = 256 * 1024;
-        let mut flash = SimulatedNorFlashBuilder::new(flash_size)
+        let timings = embedded_storage_sim::FlashTimings::new(
+            embedded_storage_sim::SpiType::QSPI,
+            false,
+            fugit::RateExtU64::MHz(125 / 2),
+            fugit::MillisDurationU64::millis(50),
+            40,
+        );
+        let mut flash = SimulatedNorFlashBuilder::with_capacity_kib(256)
             .with_logging(TransactionLogLevel::None)
             .build::<_, 1, 4, 4096>();
+        let sampler_state = Arc::clone(&app_state);
+        flash.set_snapshot_interval(1, move |snapshot| {
+            if let Ok(mut guard) = sampler_state.lock() {
+                guard.new_snapshot = Some(snapshot); // overwrite latest
+            }
+        });
 
         let mut next_workload = None;
         loop {
@@ -93,6 +110,8 @@ impl Runner {
                 workload = next;
             }
             is_running.store(true, std::sync::atomic::Ordering::SeqCst);
+            let realtime_playback = app_state.lock().unwrap().realtime_playback;
+            flash.set_realtime_playback(realtime_playback.then_some((timings, 1.0)));
             match workload {
                 Workload::Stop => {
                     return Ok(());
@@ -100,21 +119,13 @@ impl Runner {
                 Workload::Cancel => {}
                 Workload::Reset => {
                     flash.reset();
-                    let snapshot = flash.snapshot(false);
-                    if let Ok(mut guard) = app_state.lock() {
-                        guard.new_snapshot = Some(snapshot); // overwrite latest
-                    }
                 }
                 Workload::SqQueue => {
                     // run your async workload via block_on or a runtime
                     futures::executor::block_on(queue_push_full_and_pop_all(
                         &mut flash,
                         101,
-                        |flash| {
-                            let snapshot = flash.snapshot(false);
-                            if let Ok(mut guard) = app_state.lock() {
-                                guard.new_snapshot = Some(snapshot); // overwrite latest
-                            }
+                        |_| {
                             next_workload = rx.lock().unwrap().try_recv().ok();
                             next_workload.is_none()
                         },
@@ -125,11 +136,7 @@ impl Runner {
                     // run your async workload via block_on or a runtime
                     futures::executor::block_on(workloads::sequential_storage::map::map_fill(
                         &mut flash,
-                        |flash| {
-                            let snapshot = flash.snapshot(false);
-                            if let Ok(mut guard) = app_state.lock() {
-                                guard.new_snapshot = Some(snapshot); // overwrite latest
-                            }
+                        |_| {
                             next_workload = rx.lock().unwrap().try_recv().ok();
                             next_workload.is_none()
                         },
@@ -144,23 +151,13 @@ impl Runner {
                             sequential_storage::cache::NoCache,
                             &mut rand::rng(),
                             300,
-                            |flash| {
-                                let snapshot = flash.snapshot(false);
-                                if let Ok(mut guard) = app_state.lock() {
-                                    guard.new_snapshot = Some(snapshot); // overwrite latest
-                                }
+                            |_| {
                                 next_workload = rx.lock().unwrap().try_recv().ok();
                                 next_workload.is_none()
                             },
                         ),
                     ) {
                         Ok(result) => {
-                            let timings = embedded_storage_sim::FlashTimings::new(
-                                embedded_storage_sim::SpiType::QSPI,
-                                fugit::RateExtU64::MHz(125 / 2),
-                                fugit::MillisDurationU64::millis(50),
-                                40,
-                            );
                             // println!("Benchmark result: {:#?}", result);
                             if let Some(stats) = result.sequential_overwrite_stats {
                                 let data_stats = print_benchmark_results(
@@ -193,6 +190,11 @@ impl Runner {
                     }
                 }
             }
+            // the sampler only fires when the next operation starts
+            let snapshot = flash.snapshot(false);
+            if let Ok(mut guard) = app_state.lock() {
+                guard.new_snapshot = Some(snapshot); // overwrite latest
+            }
             is_running.store(false, std::sync::atomic::Ordering::SeqCst);
         }
     }
@@ -228,95 +230,6 @@ impl Runner {
     }
 }
 
-pub fn median<T: Clone>(sorted_data: &[T], default: T) -> T {
-    let len = sorted_data.len();
-    if len == 0 {
-        default
-    } else if len % 2 == 1 {
-        sorted_data[len / 2].clone()
-    } else {
-        sorted_data[len / 2 - 1].clone() // lower median
-    }
-}
-#[allow(dead_code)]
-#[derive(Debug, Clone)]
-pub struct RunData {
-    pub data: Vec<NanosDurationU64>,
-    pub min: NanosDurationU64,
-    pub max: NanosDurationU64,
-    pub avg: NanosDurationU64,
-    pub std_dev: NanosDurationU64,
-    pub median: NanosDurationU64,
-    pub p90: NanosDurationU64,
-    pub p99: NanosDurationU64,
-    pub p999: NanosDurationU64,
-    pub total: NanosDurationU64,
-    pub count: usize,
-}
-impl Default for RunData {
-    fn default() -> Self {
-        Self {
-            data: Vec::new(),
-            min: NanosDurationU64::from_ticks(0),
-            max: NanosDurationU64::from_ticks(0),
-            avg: NanosDurationU64::from_ticks(0),
-            std_dev: NanosDurationU64::from_ticks(0),
-            median: NanosDurationU64::from_ticks(0),
-            p90: NanosDurationU64::from_ticks(0),
-            p99: NanosDurationU64::from_ticks(0),
-            p999: NanosDurationU64::from_ticks(0),
-            total: NanosDurationU64::from_ticks(0),
-            count: 0,
-        }
-    }
-}
-impl From<&[NanosDurationU64]> for RunData {
-    fn from(data: &[NanosDurationU64]) -> Self {
-        let count = data.len();
-        if count == 0 {
-            return Self {
-                data: Vec::new(),
-                min: NanosDurationU64::from_ticks(0),
-                max: NanosDurationU64::from_ticks(0),
-                avg: NanosDurationU64::from_ticks(0),
-                std_dev: NanosDurationU64::from_ticks(0),
-                median: NanosDurationU64::from_ticks(0),
-                p90: NanosDurationU64::from_ticks(0),
-                p99: NanosDurationU64::from_ticks(0),
-                p999: NanosDurationU64::from_ticks(0),
-                total: NanosDurationU64::from_ticks(0),
-                count: 0,
-            };
-        }
-        let mut sorted_data: Vec<_> = data.to_vec();
-        sorted_data.sort();
-        let total = sorted_data
-            .iter()
-            .fold(NanosDurationU64::from_ticks(0), |acc, &t| acc + t);
-        let avg = total / (count as u32);
-        let std_dev = {
-            let mean = avg;
-            let var = sorted_data.iter().fold(0, |acc, &t| {
-                let diff = t.ticks() as i64 - mean.ticks() as i64;
-                acc + (diff * diff) as u64
-            }) / (count as u64);
-            NanosDurationU64::from_ticks((var as f64).sqrt() as u64)
-        };
-        Self {
-            data: data.to_vec(),
-            min: sorted_data[0],
-            max: *sorted_data.last().unwrap(),
-            avg,
-            std_dev,
-            median: median(&sorted_data, NanosDurationU64::from_ticks(0)),
-            p90: sorted_data[(count as f32 * 0.9).ceil() as usize - 1],
-            p99: sorted_data[(count as f32 * 0.99).ceil() as usize - 1],
-            p999: sorted_data[(count as f32 * 0.999).ceil() as usize - 1],
-            total,
-            count,
-        }
-    }
-}
 fn print_benchmark_results(
     name: &str,
     timings: &embedded_storage_sim::FlashTimings,
@@ -379,17 +292,6 @@ impl NorFlashApp {
     }
 }
 
-fn human_readable_bytes(bytes: usize) -> String {
-    const UNITS: [&str; 5] = ["B", "KB", "MB", "GB", "TB"];
-    let mut size = bytes as f64;
-    let mut unit_index = 0;
-    while size >= 1024.0 && unit_index < UNITS.len() - 1 {
-        size /= 1024.0;
-        unit_index += 1;
-    }
-    format!("{:.2} {}", size, UNITS[unit_index])
-}
-
 impl eframe::App for NorFlashApp {
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
         // Receive pending snapshots (non-blocking)
@@ -423,6 +325,10 @@ impl eframe::App for NorFlashApp {
                 if ui.button("Run map store benchmark").clicked() {
                     self.runner.start_workload(Workload::SqBenchmarkMapStore);
                 }
+                ui.checkbox(
+                    &mut self.state.lock().unwrap().realtime_playback,
+                    "Real-time playback",
+                );
                 if self.runner.is_working() {
                     if ui.button("Cancel").clicked() {
                         self.runner.start_workload(Workload::Cancel);
@@ -521,7 +427,7 @@ fn draw_page_grid(ui: &mut egui::Ui, snapshot: &FlashSnapshot) -> InnerResponse<
     let rows = (pages + cols - 1) / cols;
 
     let cell_size = egui::vec2(35.0, 35.0);
-    let red_cycle_count = page_cycles.iter().copied().max().unwrap_or(100).max(20); // cycles at which cell is fully red
+    let red_cycle_count = wear_color_scale(page_cycles); // cycles at which cell is fully red
     let font = FontId::proportional(11.0);
 
     egui::Grid::new("page_grid")
@@ -540,11 +446,8 @@ fn draw_page_grid(ui: &mut egui::Ui, snapshot: &FlashSnapshot) -> InnerResponse<
                         ui.allocate_exact_size(cell_size, egui::Sense::hover());
                     } else {
                         let cycles = page_cycles[idx];
-                        let norm =
-                            (cycles.min(red_cycle_count) as f32) / red_cycle_count.max(1) as f32;
-                        let intensity = (norm * 255.0) as u8;
-
-                        let color = egui::Color32::from_rgb(intensity, 255 - intensity, 0);
+                        let [r, g, b] = wear_color(cycles, red_cycle_count);
+                        let color = egui::Color32::from_rgb(r, g, b);
 
                         egui::Frame::NONE
                             .fill(color)
@@ -0,0 +1,82 @@
+/// Cycle count at which a page is drawn fully red in a wear heatmap.
+///
+/// This is the highest count in `page_cycles`, but at least 20 so a few
+/// erases don't already look worn out.
+pub fn wear_color_scale(page_cycles: &[u32]) -> u32 {
+    page_cycles.iter().copied().max().unwrap_or(100).max(20)
+}
+
+/// Heatmap color of a page with `cycles` erase cycles as RGB, from green
+/// (unused) to red (at `scale` cycles, see [`wear_color_scale`]).
+pub fn wear_color(cycles: u32, scale: u32) -> [u8; 3] {
+    let norm = cycles.min(scale) as f32 / scale.max(1) as f32;
+    let intensity = (norm * 255.0) as u8;
+    [intensity, 255 - intensity, 0]
+}
+
+#[cfg(feature = "image")]
+mod png_output {
+    use alloc::vec;
+    use std::{
+        fs::File,
+        io::{self, BufWriter},
+        path::Path,
+    };
+
+    use super::{wear_color, wear_color_scale};
+    use crate::SimulatedNorFlash;
+
+    /// Edge length of one page in the rendered heatmap, in pixels.
+    const CELL_SIZE: usize = 16;
+
+    impl<O: Clone, const RS: usize, const WS: usize, const ES: usize> SimulatedNorFlash<O, RS, WS, ES> {
+        /// Render the per-page erase cycles as a heatmap PNG at `path`.
+        ///
+        /// Pages are laid out row by row in `cols` columns of 16×16 pixel
+        /// cells, colored with [`wear_color`] like the GUI's page grid.
+        /// Cells past the last page stay white. Useful for reports in CI:
+        ///
+        /// ```
+        /// use embedded_storage::nor_flash::NorFlash;
+        /// use embedded_storage_sim::SimulatedNorFlash;
+        ///
+        /// let mut flash: SimulatedNorFlash = SimulatedNorFlash::new(10 * 4096);
+        /// flash.erase(0, 4096).unwrap();
+        /// let path = std::env::temp_dir().join("embedded-storage-sim-wear.png");
+        /// flash.render_wear_png(&path, 4).unwrap();
+        /// assert!(std::fs::metadata(&path).unwrap().len() > 0);
+        /// ```
+        ///
+        /// Panics if `cols` is zero.
+        pub fn render_wear_png(&self, path: impl AsRef<Path>, cols: usize) -> io::Result<()> {
+            assert!(cols > 0);
+            let page_cycles = self.page_erase_cycles();
+            let rows = page_cycles.len().div_ceil(cols).max(1);
+            let (width, height) = (cols * CELL_SIZE, rows * CELL_SIZE);
+            let scale = wear_color_scale(page_cycles);
+            let mut pixels = vec![0xff; width * height * 3];
+            for (index, &cycles) in page_cycles.iter().enumerate() {
+                let color = wear_color(cycles, scale);
+                let (x, y) = (index % cols * CELL_SIZE, index / cols * CELL_SIZE);
+                for row in y..y + CELL_SIZE {
+                    let start = (row * width + x) * 3;
+                    for pixel in pixels[start..start + CELL_SIZE * 3].chunks_exact_mut(3) {
+                        pixel.copy_from_slice(&color);
+                    }
+                }
+            }
+
+            let mut encoder = png::Encoder::new(
+                BufWriter::new(File::create(path)?),
+                width as u32,
+                height as u32,
+            );
+            encoder.set_color(png::ColorType::Rgb);
+            encoder.set_depth(png::BitDepth::Eight);
+            let mut writer = encoder.write_header()?;
+            writer.write_image_data(&pixels)?;
+            writer.finish()?;
+            Ok(())
+        }
+    }
+}
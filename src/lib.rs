@@ -13,11 +13,51 @@
 //! - Use it anywhere a NOR flash implementing the storage traits is needed.
 //! - Inspect statistics or compute timing estimates using `FlashTimings`.
 //! - Capture a `FlashSnapshot` for UI or diagnostics.
+//!
+//! # Features
+//!
+//! The simulator core is `#![no_std]` and only requires `alloc`. The
+//! following Cargo features add functionality on top:
+//! - `std` (default): Enables `std`-only helpers, such as seeding the failure
+//!   RNG from OS entropy when no fixed seed is given, and the headless
+//!   [`Benchmark`] summaries. Without it, an unseeded flash uses the seed `0`.
+//! - `retention`: Temperature-dependent data retention loss with
+//!   [`RetentionModel`] and `SimulatedNorFlash::advance_time`. Implies `std`.
+//! - `image`: Render the page wear heatmap headlessly with
+//!   `SimulatedNorFlash::render_wear_png`. Implies `std`.
+//! - `sequential-storage` (default): Example workloads for `sequential-storage`.
+//! - `gui` (default): The `eframe`/`egui` visualizer binary. Implies `std`.
+//!
+//! # Async
+//!
+//! The async trait methods take `&mut self` and complete on their first
+//! poll, so at most one operation is ever in flight per flash and there is
+//! no concurrency to measure. Code that shares a flash between tasks has to
+//! serialize access (e.g. behind a mutex), just like with a real device.
 
-use std::ops::{Add, Sub};
+#![no_std]
+
+extern crate alloc;
+#[cfg(feature = "std")]
+extern crate std;
+
+use alloc::{
+    boxed::Box,
+    format,
+    string::{String, ToString},
+    sync::Arc,
+    vec,
+    vec::Vec,
+};
+use core::{
+    fmt::{self, Display},
+    hash::Hash,
+    ops::{Add, AddAssign, Deref, DerefMut, Range, Sub},
+};
 
 use embedded_storage_async::nor_flash::{MultiwriteNorFlash, NorFlash, ReadNorFlash};
-use rand::SeedableRng;
+use hashbrown::HashMap;
+use rand::seq::index;
 
 /// SPI line configuration used to derive effective bus throughput.
 ///
@@ -27,6 +67,7 @@ pub enum SpiType {
     SPI = 1,
     DSPI = 2,
     QSPI = 4,
+    OSPI = 8,
 }
 /// Helper to estimate operation durations for a given bus and device.
 ///
@@ -34,38 +75,143 @@ pub enum SpiType {
 /// methods on [`SimulatedNorFlash`] to get read/write/erase/total estimates.
 #[derive(Clone, Copy, Debug)]
 pub struct FlashTimings {
+    bytes_per_second: u64,
     read_time_per_byte: fugit::NanosDurationU64,
     write_time_per_byte: fugit::NanosDurationU64,
     page_erase_time: fugit::MillisDurationU64,
+    subsector_erase_time: fugit::MillisDurationU64,
     read_access_overhead: fugit::NanosDurationU64,
     write_access_overhead: fugit::NanosDurationU64,
     erase_access_overhead: fugit::NanosDurationU64,
+    program_busy_time: fugit::NanosDurationU64,
+    erase_busy_time: fugit::NanosDurationU64,
+    supply_voltage: f32,
+    read_current: f32,
+    program_current: f32,
+    erase_current: f32,
 }
 
 impl FlashTimings {
     /// Create timing parameters from bus type/frequency and device properties.
     ///
-    /// - `spi_type`: Number of active data lanes (`SPI`/`DSPI`/`QSPI`).
+    /// - `spi_type`: Number of active data lanes (`SPI`/`DSPI`/`QSPI`/`OSPI`).
+    /// - `ddr`: Double data rate, transferring on both clock edges.
     /// - `flash_frequency`: I/O clock frequency of the SPI bus.
     /// - `page_erase_time`: Typical erase duration for a single sector erase (typically 4 KiB).
     /// - `access_overhead_cycles`: Extra bus cycles per access (command, address, dummy cycles etc.).
+    ///
+    /// Reads and programs both run at bus speed. Use [`FlashTimings::builder`]
+    /// to configure them independently.
+    ///
+    /// ```
+    /// use embedded_storage_sim::{FlashTimings, SpiType};
+    /// use fugit::{MillisDurationU64, RateExtU64};
+    ///
+    /// let timings = FlashTimings::new(SpiType::OSPI, true, 100.MHz(), MillisDurationU64::millis(40), 0);
+    /// assert_eq!(timings.bytes_per_second(), 200_000_000);
+    /// assert_eq!(timings.read_time(400, 1).ticks(), 2_000);
+    /// ```
     pub fn new(
         spi_type: SpiType,
+        ddr: bool,
         flash_frequency: fugit::MegahertzU64,
         page_erase_time: fugit::MillisDurationU64,
         access_overhead_cycles: u32,
     ) -> Self {
-        let bytes_per_second = flash_frequency / (8 / spi_type as u32);
-        let time_per_byte = fugit::NanosDurationU64::from_rate(bytes_per_second);
-        let overhead_time =
-            fugit::NanosDurationU64::from_rate(flash_frequency) * access_overhead_cycles;
+        Self::builder(spi_type, ddr, flash_frequency, page_erase_time)
+            .with_access_overhead_cycles(access_overhead_cycles)
+            .build()
+    }
+    /// Start a [`FlashTimingsBuilder`] for parts with asymmetric timings.
+    ///
+    /// Reads and programs initially run at bus speed without access
+    /// overhead; use the builder methods to override individual parameters.
+    pub fn builder(
+        spi_type: SpiType,
+        ddr: bool,
+        flash_frequency: fugit::MegahertzU64,
+        page_erase_time: fugit::MillisDurationU64,
+    ) -> FlashTimingsBuilder {
+        let transfers_per_cycle = if ddr { 2 } else { 1 };
+        let bytes_per_second = flash_frequency.to_Hz() * spi_type as u64 * transfers_per_cycle / 8;
+        let time_per_byte =
+            fugit::NanosDurationU64::from_rate(fugit::HertzU64::from_raw(bytes_per_second));
+        let no_overhead = fugit::NanosDurationU64::from_ticks(0);
+        FlashTimingsBuilder {
+            flash_frequency,
+            timings: Self {
+                bytes_per_second,
+                read_time_per_byte: time_per_byte,
+                write_time_per_byte: time_per_byte,
+                page_erase_time,
+                subsector_erase_time: page_erase_time,
+                read_access_overhead: no_overhead,
+                write_access_overhead: no_overhead,
+                erase_access_overhead: no_overhead,
+                program_busy_time: no_overhead,
+                erase_busy_time: no_overhead,
+                supply_voltage: 0.0,
+                read_current: 0.0,
+                program_current: 0.0,
+                erase_current: 0.0,
+            },
+        }
+    }
+    /// Raw bus throughput in bytes per second, from the bus type and frequency.
+    ///
+    /// This is the transfer rate before any access overhead. It stays the
+    /// same when the read or program throughput is overridden in the
+    /// [`FlashTimingsBuilder`].
+    ///
+    /// ```
+    /// use embedded_storage_sim::{FlashTimings, SpiType};
+    /// use fugit::{MillisDurationU64, RateExtU64};
+    ///
+    /// let timings = FlashTimings::new(SpiType::QSPI, false, 50.MHz(), MillisDurationU64::millis(40), 40);
+    /// assert_eq!(timings.bytes_per_second(), 25_000_000);
+    /// assert!((timings.effective_mib_per_second() - 23.84).abs() < 0.01);
+    /// ```
+    pub fn bytes_per_second(&self) -> u64 {
+        self.bytes_per_second
+    }
+    /// [`bytes_per_second`](Self::bytes_per_second) in MiB/s.
+    pub fn effective_mib_per_second(&self) -> f64 {
+        self.bytes_per_second as f64 / (1024.0 * 1024.0)
+    }
+    /// A copy with erase and program times scaled by `factor`.
+    ///
+    /// Applies to the page and sub-sector erase times and the program and
+    /// erase busy times,
+    /// which depend on the cell physics. Bus transfers and access overheads
+    /// are unaffected. Use a factor above 1 to estimate worst-case timing at
+    /// temperature or supply voltage corners from the datasheet.
+    ///
+    /// ```
+    /// use embedded_storage_sim::{FlashTimings, SpiType};
+    /// use fugit::{MillisDurationU64, RateExtU64};
+    ///
+    /// let typical = FlashTimings::new(SpiType::QSPI, false, 50.MHz(), MillisDurationU64::millis(40), 40);
+    /// let hot = typical.derated(1.5);
+    /// assert_eq!(hot.erase_time(1, 0), MillisDurationU64::millis(60));
+    /// assert_eq!(hot.read_time(256, 1), typical.read_time(256, 1));
+    /// ```
+    pub fn derated(&self, factor: f32) -> Self {
+        // no `f32::round` without std
+        let scale = |ticks: u64| (ticks as f64 * factor as f64 + 0.5) as u64;
         Self {
-            read_time_per_byte: time_per_byte,
-            write_time_per_byte: time_per_byte,
-            page_erase_time,
-            read_access_overhead: overhead_time,
-            write_access_overhead: overhead_time,
-            erase_access_overhead: overhead_time,
+            page_erase_time: fugit::MillisDurationU64::from_ticks(scale(
+                self.page_erase_time.ticks(),
+            )),
+            subsector_erase_time: fugit::MillisDurationU64::from_ticks(scale(
+                self.subsector_erase_time.ticks(),
+            )),
+            program_busy_time: fugit::NanosDurationU64::from_ticks(scale(
+                self.program_busy_time.ticks(),
+            )),
+            erase_busy_time: fugit::NanosDurationU64::from_ticks(scale(
+                self.erase_busy_time.ticks(),
+            )),
+            ..*self
         }
     }
     /// Estimated read time for `total_bytes` over `accesses` logical operations.
@@ -73,31 +219,267 @@ impl FlashTimings {
         self.read_time_per_byte * total_bytes as u32 + self.read_access_overhead * accesses
     }
     /// Estimated program time for `total_bytes` over `accesses` logical operations.
+    ///
+    /// Each program command adds its access overhead and busy time.
     pub fn write_time(&self, total_bytes: usize, accesses: u32) -> fugit::NanosDurationU64 {
-        self.write_time_per_byte * total_bytes as u32 + self.write_access_overhead * accesses
+        self.write_time_per_byte * total_bytes as u32
+            + (self.write_access_overhead + self.program_busy_time) * accesses
     }
     /// Estimated erase time for `pages` erase units and `accesses` commands.
+    ///
+    /// Each erased page costs `page_erase_time`, while each erase command
+    /// (which may span several pages) adds one access overhead and busy time.
+    /// The sum is rounded down to whole milliseconds; [`total_time`] keeps
+    /// the sub-millisecond part:
+    ///
+    /// ```
+    /// use embedded_storage_sim::{FlashStats, FlashTimings, SpiType};
+    /// use fugit::{ExtU64, RateExtU64};
+    ///
+    /// let timings = FlashTimings::builder(SpiType::SPI, false, 8.MHz(), 50.millis())
+    ///     .with_erase_busy_time(600.micros())
+    ///     .build();
+    /// assert_eq!(timings.erase_time(2, 1).to_micros(), 100_000);
+    /// assert_eq!(timings.erase_time(0, 3).to_micros(), 1_000);
+    /// let stats = FlashStats { pages_erased: 2, erase_accesses: 1, ..Default::default() };
+    /// assert_eq!(timings.total_time(&stats).to_micros(), 100_600);
+    /// ```
+    ///
+    /// [`total_time`]: Self::total_time
     pub fn erase_time(&self, pages: usize, accesses: u32) -> fugit::MillisDurationU64 {
-        self.page_erase_time * pages as u32 + (self.erase_access_overhead * accesses).convert()
+        self.erase_time_nanos(pages, accesses).convert()
+    }
+    /// [`erase_time`](Self::erase_time) without rounding to milliseconds.
+    fn erase_time_nanos(&self, pages: usize, accesses: u32) -> fugit::NanosDurationU64 {
+        let page_erase_time: fugit::NanosDurationU64 = self.page_erase_time.convert();
+        page_erase_time * pages as u32
+            + (self.erase_access_overhead + self.erase_busy_time) * accesses
+    }
+    /// Estimated time to erase `subsectors` sub-sectors, excluding the
+    /// per-command overhead counted by [`erase_time`](Self::erase_time).
+    pub fn subsector_erase_time(&self, subsectors: usize) -> fugit::MillisDurationU64 {
+        self.subsector_erase_time * subsectors as u32
     }
     /// Combined estimate across reads, writes and erases.
     pub fn total_time(&self, stats: &FlashStats) -> fugit::NanosDurationU64 {
         self.read_time(stats.bytes_read, stats.read_accesses as u32)
             + self.write_time(stats.bytes_written, stats.write_accesses as u32)
-            + self
-                .erase_time(stats.pages_erased, stats.erase_accesses as u32)
-                .convert()
+            + self.erase_time_nanos(stats.pages_erased, stats.erase_accesses as u32)
+            + self.subsector_erase_time(stats.subsectors_erased).convert()
+    }
+    /// Estimated read energy in microjoules, see [`read_time`](Self::read_time).
+    ///
+    /// All energy estimates are zero unless supply voltage and currents were
+    /// set via [`FlashTimingsBuilder::with_supply`].
+    pub fn read_energy(&self, total_bytes: usize, accesses: u32) -> f64 {
+        self.energy(self.read_current, self.read_time(total_bytes, accesses))
+    }
+    /// Estimated program energy in microjoules, see [`write_time`](Self::write_time).
+    pub fn write_energy(&self, total_bytes: usize, accesses: u32) -> f64 {
+        self.energy(self.program_current, self.write_time(total_bytes, accesses))
+    }
+    /// Estimated erase energy in microjoules, see [`erase_time`](Self::erase_time).
+    pub fn erase_energy(&self, pages: usize, accesses: u32) -> f64 {
+        self.energy(self.erase_current, self.erase_time_nanos(pages, accesses))
+    }
+    /// Combined energy estimate in microjoules across reads, writes and erases.
+    pub fn total_energy(&self, stats: &FlashStats) -> f64 {
+        self.read_energy(stats.bytes_read, stats.read_accesses as u32)
+            + self.write_energy(stats.bytes_written, stats.write_accesses as u32)
+            + self.erase_energy(stats.pages_erased, stats.erase_accesses as u32)
+            + self.energy(
+                self.erase_current,
+                self.subsector_erase_time(stats.subsectors_erased).convert(),
+            )
+    }
+    /// Energy in microjoules drawn at `current` mA over `time` (mW * ms = µJ).
+    fn energy(&self, current: f32, time: fugit::NanosDurationU64) -> f64 {
+        self.supply_voltage as f64 * current as f64 * time.ticks() as f64 / 1_000_000.0
+    }
+}
+
+/// Builder for [`FlashTimings`] with independent read and program parameters.
+///
+/// Created by [`FlashTimings::builder`].
+#[derive(Clone, Copy, Debug)]
+pub struct FlashTimingsBuilder {
+    flash_frequency: fugit::MegahertzU64,
+    timings: FlashTimings,
+}
+impl FlashTimingsBuilder {
+    /// Set the read throughput in bytes per second.
+    pub fn with_read_throughput(mut self, bytes_per_second: fugit::HertzU64) -> Self {
+        self.timings.read_time_per_byte = fugit::NanosDurationU64::from_rate(bytes_per_second);
+        self
+    }
+    /// Set the program throughput in bytes per second.
+    ///
+    /// On many parts this is dominated by the internal programming time
+    /// rather than the bus speed.
+    pub fn with_program_throughput(mut self, bytes_per_second: fugit::HertzU64) -> Self {
+        self.timings.write_time_per_byte = fugit::NanosDurationU64::from_rate(bytes_per_second);
+        self
+    }
+    /// Set the extra bus cycles per access (command, address, dummy cycles etc.).
+    pub fn with_access_overhead_cycles(mut self, cycles: u32) -> Self {
+        let overhead = self.cycles(cycles);
+        self.timings.read_access_overhead = overhead;
+        self.timings.write_access_overhead = overhead;
+        self.timings.erase_access_overhead = overhead;
+        self
+    }
+    /// Set the extra bus cycles per read command (command, address, dummy cycles).
+    ///
+    /// Overrides the read part of [`with_access_overhead_cycles`]. Reads
+    /// usually pay for dummy cycles that program and erase commands don't:
+    ///
+    /// ```
+    /// use embedded_storage_sim::{FlashTimings, SpiType};
+    /// use fugit::{ExtU64, RateExtU64};
+    ///
+    /// // 1 byte per µs, 125 ns per bus cycle.
+    /// let timings = FlashTimings::builder(SpiType::SPI, false, 8.MHz(), 50.millis())
+    ///     .with_read_overhead_cycles(8)
+    ///     .with_program_overhead_cycles(16)
+    ///     .with_erase_overhead_cycles(80_000)
+    ///     .build();
+    /// assert_eq!(timings.read_time(1, 1).to_nanos(), 2_000);
+    /// assert_eq!(timings.write_time(1, 1).to_nanos(), 3_000);
+    /// assert_eq!(timings.erase_time(1, 1).to_millis(), 60);
+    /// ```
+    ///
+    /// [`with_access_overhead_cycles`]: Self::with_access_overhead_cycles
+    pub fn with_read_overhead_cycles(mut self, cycles: u32) -> Self {
+        self.timings.read_access_overhead = self.cycles(cycles);
+        self
+    }
+    /// Set the extra bus cycles per program command.
+    ///
+    /// Overrides the program part of [`with_access_overhead_cycles`](Self::with_access_overhead_cycles).
+    pub fn with_program_overhead_cycles(mut self, cycles: u32) -> Self {
+        self.timings.write_access_overhead = self.cycles(cycles);
+        self
+    }
+    /// Set the extra bus cycles per erase command.
+    ///
+    /// Overrides the erase part of [`with_access_overhead_cycles`](Self::with_access_overhead_cycles).
+    pub fn with_erase_overhead_cycles(mut self, cycles: u32) -> Self {
+        self.timings.erase_access_overhead = self.cycles(cycles);
+        self
+    }
+    /// Set the time to erase one sub-sector, see
+    /// [`SimulatedNorFlash::erase_subsector`]. Defaults to the page erase time.
+    pub fn with_subsector_erase_time(mut self, erase_time: fugit::MillisDurationU64) -> Self {
+        self.timings.subsector_erase_time = erase_time;
+        self
+    }
+    /// Set the time the chip stays busy after each program command (tWR/tPP).
+    ///
+    /// ```
+    /// use embedded_storage_sim::{FlashTimings, SpiType};
+    /// use fugit::{ExtU64, RateExtU64};
+    ///
+    /// // 1 byte per µs
+    /// let timings = FlashTimings::builder(SpiType::SPI, false, 8.MHz(), 50.millis())
+    ///     .with_program_busy_time(700.micros())
+    ///     .build();
+    /// assert_eq!(timings.write_time(256, 1).to_micros(), 256 + 700);
+    /// assert_eq!(timings.write_time(256, 2).to_micros(), 256 + 2 * 700);
+    /// assert_eq!(timings.read_time(256, 1).to_micros(), 256);
+    /// ```
+    pub fn with_program_busy_time(mut self, busy_time: fugit::NanosDurationU64) -> Self {
+        self.timings.program_busy_time = busy_time;
+        self
+    }
+    /// Set the time the chip stays busy after each erase command, on top of
+    /// the per-page erase time.
+    pub fn with_erase_busy_time(mut self, busy_time: fugit::NanosDurationU64) -> Self {
+        self.timings.erase_busy_time = busy_time;
+        self
+    }
+    /// Set the supply voltage in volts and the active current in mA while
+    /// reading, programming and erasing, for the `*_energy` estimates.
+    pub fn with_supply(
+        mut self,
+        voltage: f32,
+        read_current: f32,
+        program_current: f32,
+        erase_current: f32,
+    ) -> Self {
+        self.timings.supply_voltage = voltage;
+        self.timings.read_current = read_current;
+        self.timings.program_current = program_current;
+        self.timings.erase_current = erase_current;
+        self
+    }
+    /// Build the [`FlashTimings`].
+    pub fn build(self) -> FlashTimings {
+        self.timings
+    }
+    /// Duration of `cycles` bus clock cycles.
+    fn cycles(&self, cycles: u32) -> fugit::NanosDurationU64 {
+        fugit::NanosDurationU64::from_rate(self.flash_frequency) * cycles
     }
 }
 
+/// Accumulated access counters, as returned by [`SimulatedNorFlash::stats`].
+///
+/// Erases are tracked twice: `pages_erased` counts erase units (sectors),
+/// while `erase_accesses` counts erase commands. Erasing three pages with a
+/// single `erase` call yields `pages_erased == 3` and `erase_accesses == 1`:
+///
+/// ```
+/// use embedded_storage::nor_flash::NorFlash;
+/// use embedded_storage_sim::SimulatedNorFlash;
+///
+/// let mut flash: SimulatedNorFlash = SimulatedNorFlash::new(4 * 4096);
+/// flash.erase(4096, 4 * 4096).unwrap();
+/// let stats = flash.stats();
+/// assert!(stats.pages_erased == 3 && stats.erase_accesses == 1);
+/// ```
+///
+/// All counters, both here and in the flash itself (see
+/// [`SimulatedNorFlash::restore_stats`]), saturate at `usize::MAX` instead
+/// of overflowing, which can matter for very long runs on 32-bit hosts.
+/// Adding stats saturates the same way:
+///
+/// ```
+/// use embedded_storage_sim::FlashStats;
+///
+/// let almost_full = FlashStats {
+///     bytes_written: usize::MAX - 1,
+///     ..Default::default()
+/// };
+/// let more = FlashStats {
+///     bytes_written: 4096,
+///     write_accesses: 1,
+///     ..Default::default()
+/// };
+/// let total = &almost_full + &more;
+/// assert_eq!(total.bytes_written, usize::MAX);
+/// assert_eq!(total.write_accesses, 1);
+/// ```
 #[derive(Clone, Debug, Default)]
 pub struct FlashStats {
+    /// Number of bytes read.
     pub bytes_read: usize,
+    /// Number of bytes written.
     pub bytes_written: usize,
+    /// Number of program units touched by writes, see
+    /// [`with_program_unit`](SimulatedNorFlashBuilder::with_program_unit).
+    pub program_units_written: usize,
+    /// Number of erase units (sectors) erased.
     pub pages_erased: usize,
+    /// Number of sub-sectors erased with
+    /// [`erase_subsector`](SimulatedNorFlash::erase_subsector).
+    pub subsectors_erased: usize,
+    /// Number of read commands.
     pub read_accesses: usize,
+    /// Number of write (program) commands.
     pub write_accesses: usize,
+    /// Number of erase commands, regardless of how many pages each covered.
     pub erase_accesses: usize,
+    /// Number of high level operations started.
     pub total_operations: usize,
 }
 impl Add<&FlashStats> for &FlashStats {
@@ -106,13 +488,19 @@ impl Add<&FlashStats> for &FlashStats {
     #[inline]
     fn add(self, other: &FlashStats) -> FlashStats {
         FlashStats {
-            bytes_read: self.bytes_read + other.bytes_read,
-            bytes_written: self.bytes_written + other.bytes_written,
-            pages_erased: self.pages_erased + other.pages_erased,
-            read_accesses: self.read_accesses + other.read_accesses,
-            write_accesses: self.write_accesses + other.write_accesses,
-            erase_accesses: self.erase_accesses + other.erase_accesses,
-            total_operations: self.total_operations + other.total_operations,
+            bytes_read: self.bytes_read.saturating_add(other.bytes_read),
+            bytes_written: self.bytes_written.saturating_add(other.bytes_written),
+            program_units_written: self
+                .program_units_written
+                .saturating_add(other.program_units_written),
+            pages_erased: self.pages_erased.saturating_add(other.pages_erased),
+            subsectors_erased: self
+                .subsectors_erased
+                .saturating_add(other.subsectors_erased),
+            read_accesses: self.read_accesses.saturating_add(other.read_accesses),
+            write_accesses: self.write_accesses.saturating_add(other.write_accesses),
+            erase_accesses: self.erase_accesses.saturating_add(other.erase_accesses),
+            total_operations: self.total_operations.saturating_add(other.total_operations),
         }
     }
 }
@@ -124,7 +512,9 @@ impl Sub<&FlashStats> for &FlashStats {
         FlashStats {
             bytes_read: self.bytes_read - other.bytes_read,
             bytes_written: self.bytes_written - other.bytes_written,
+            program_units_written: self.program_units_written - other.program_units_written,
             pages_erased: self.pages_erased - other.pages_erased,
+            subsectors_erased: self.subsectors_erased - other.subsectors_erased,
             read_accesses: self.read_accesses - other.read_accesses,
             write_accesses: self.write_accesses - other.write_accesses,
             erase_accesses: self.erase_accesses - other.erase_accesses,
@@ -132,6 +522,168 @@ impl Sub<&FlashStats> for &FlashStats {
         }
     }
 }
+/// One-line summary, e.g.
+/// `reads=1.20 MB/3400 acc, writes=512.00 KB/800 acc, erases=12 pages/12 acc, ops=400`.
+impl Display for FlashStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "reads={}/{} acc, writes={}/{} acc, erases={} pages/{} acc, ",
+            human_readable_bytes(self.bytes_read),
+            self.read_accesses,
+            human_readable_bytes(self.bytes_written),
+            self.write_accesses,
+            self.pages_erased,
+            self.erase_accesses,
+        )?;
+        if self.subsectors_erased != 0 {
+            write!(f, "subsectors={}, ", self.subsectors_erased)?;
+        }
+        write!(f, "ops={}", self.total_operations)
+    }
+}
+impl FlashStats {
+    /// Multi-line breakdown of all counters, one per line.
+    ///
+    /// ```
+    /// use embedded_storage_sim::FlashStats;
+    ///
+    /// let stats = FlashStats {
+    ///     bytes_read: 2048,
+    ///     read_accesses: 2,
+    ///     ..Default::default()
+    /// };
+    /// assert_eq!(
+    ///     stats.to_string(),
+    ///     "reads=2.00 KB/2 acc, writes=0.00 B/0 acc, erases=0 pages/0 acc, ops=0"
+    /// );
+    /// assert!(stats.pretty().starts_with("read:          2.00 KB in 2 accesses\n"));
+    ///
+    /// let stats = FlashStats {
+    ///     subsectors_erased: 3,
+    ///     erase_accesses: 1,
+    ///     ..Default::default()
+    /// };
+    /// assert_eq!(
+    ///     stats.to_string(),
+    ///     "reads=0.00 B/0 acc, writes=0.00 B/0 acc, erases=0 pages/1 acc, subsectors=3, ops=0"
+    /// );
+    /// assert!(stats.pretty().contains("\nsub-sectors:   3\n"));
+    /// ```
+    pub fn pretty(&self) -> String {
+        format!(
+            "read:          {} in {} accesses\n\
+             written:       {} in {} accesses\n\
+             program units: {}\n\
+             erased:        {} pages in {} accesses\n\
+             sub-sectors:   {}\n\
+             operations:    {}",
+            human_readable_bytes(self.bytes_read),
+            self.read_accesses,
+            human_readable_bytes(self.bytes_written),
+            self.write_accesses,
+            self.program_units_written,
+            self.pages_erased,
+            self.erase_accesses,
+            self.subsectors_erased,
+            self.total_operations
+        )
+    }
+}
+
+/// Format a byte count with a binary unit, e.g. `1.50 KB` for 1536 bytes.
+pub fn human_readable_bytes(bytes: usize) -> String {
+    const UNITS: [&str; 5] = ["B", "KB", "MB", "GB", "TB"];
+    let mut size = bytes as f64;
+    let mut unit_index = 0;
+    while size >= 1024.0 && unit_index < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit_index += 1;
+    }
+    format!("{:.2} {}", size, UNITS[unit_index])
+}
+
+/// Wear and defect state of a single erase unit, as returned by [`SimulatedNorFlash::pages`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PageInfo {
+    /// Page index (offset / `ERASE_SIZE`).
+    pub index: usize,
+    /// Number of times the page was erased.
+    pub erase_cycles: u32,
+    /// Number of stuck bits within the page. A bit stuck both ways counts
+    /// once, like in [`SimulatedNorFlash::stuck_bits`].
+    pub stuck_bit_count: usize,
+}
+
+/// Remaining safe erase cycles, as returned by [`SimulatedNorFlash::endurance_remaining`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct EnduranceReport {
+    /// Safe erase cycles left, summed over all pages.
+    pub total_remaining: u64,
+    /// Safe erase cycles left on the most worn page.
+    pub worst_page_remaining: u32,
+    /// Index of the most worn page.
+    pub worst_page_index: usize,
+}
+
+/// Error of [`SimulatedNorFlash::run_within`] when the simulated time
+/// exceeded the budget.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DeadlineExceeded {
+    /// Simulated time spent, including the step that exceeded the budget.
+    pub elapsed: fugit::NanosDurationU64,
+    /// Number of completed steps, including the one that exceeded the budget.
+    pub steps: usize,
+}
+
+impl Display for DeadlineExceeded {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "deadline exceeded after {} steps ({} us)",
+            self.steps,
+            self.elapsed.to_micros()
+        )
+    }
+}
+
+impl core::error::Error for DeadlineExceeded {}
+
+/// Wear relative to endurance, as returned by [`SimulatedNorFlash::wear_score`].
+///
+/// A page's pressure is its erase cycles divided by its
+/// [`page_endurance`](SimulatedNorFlash::page_endurance), so `1.0` means
+/// the page reached its safe limit.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct WearScore {
+    /// Pressure of the most worn page, above `1.0` once it is past its limit.
+    pub max_pressure: f64,
+    /// Mean pressure over all pages, each clamped to `1.0`.
+    pub average_pressure: f64,
+}
+
+/// How reads and writes are counted as accesses, see
+/// [`SimulatedNorFlashBuilder::with_access_granularity`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum AccessGranularity {
+    /// One access per trait call.
+    #[default]
+    PerCall,
+    /// One access per `ERASE_SIZE` page touched.
+    PerPage,
+    /// One access per aligned block of this many bytes touched, e.g. 256
+    /// byte program pages.
+    PerBlock(usize),
+}
+
+/// How free space is measured, see [`SimulatedNorFlash::free_bytes`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FreeSpace {
+    /// All erased bytes, wherever they are.
+    Total,
+    /// The longest run of consecutive erased bytes.
+    Contiguous,
+}
 
 /// Builder for [`SimulatedNorFlash`], including logging and simple wear-out.
 ///
@@ -141,20 +693,82 @@ pub struct SimulatedNorFlashBuilder {
     size: usize,
     minimum_erase_cycles: u32,
     bit_failure_every_x_erases: u32,
+    failure_model: Option<FailureModelFactory>,
     rng_seed: Option<u64>,
     log_level: TransactionLogLevel,
+    operation_history: bool,
+    ram_semantics: bool,
+    metadata_only: bool,
+    strict_writes: bool,
+    rewrite_detection: bool,
+    sector_crcs: bool,
+    skip_erased_pages: bool,
+    erase_failure_rate: f32,
+    uninitialized_poison: Option<Vec<u8>>,
+    write_verify: bool,
+    #[cfg(feature = "retention")]
+    retention: Option<RetentionModel>,
+    subsector_size: Option<usize>,
+    access_granularity: AccessGranularity,
+    read_cache: Option<(usize, usize)>,
+    program_unit: Option<usize>,
+    failure_weight: Option<FailureWeight>,
+    program_failure_rate: f32,
+    initial_page_cycles: Option<Vec<u32>>,
+    unmapped_regions: Vec<Range<u32>>,
 }
 impl SimulatedNorFlashBuilder {
-    /// Start a builder for a flash of `size` bytes.
+    /// Start a builder for a flash of exactly `size` bytes.
+    ///
+    /// [`build`](Self::build) panics if `size` is not a multiple of `ERASE_SIZE`.
     pub fn new(size: usize) -> Self {
         Self {
             size,
             minimum_erase_cycles: u32::MAX,
             bit_failure_every_x_erases: u32::MAX,
+            failure_model: None,
             rng_seed: None,
             log_level: TransactionLogLevel::None,
+            operation_history: false,
+            ram_semantics: false,
+            metadata_only: false,
+            strict_writes: false,
+            rewrite_detection: false,
+            sector_crcs: false,
+            skip_erased_pages: false,
+            erase_failure_rate: 0.0,
+            uninitialized_poison: None,
+            write_verify: false,
+            #[cfg(feature = "retention")]
+            retention: None,
+            subsector_size: None,
+            access_granularity: AccessGranularity::PerCall,
+            read_cache: None,
+            program_unit: None,
+            failure_weight: None,
+            program_failure_rate: 0.0,
+            initial_page_cycles: None,
+            unmapped_regions: Vec::new(),
         }
     }
+    /// Start a builder for a flash of `kib` KiB.
+    ///
+    /// Like [`new`](Self::new), [`build`](Self::build) panics if the size is
+    /// not a multiple of `ERASE_SIZE`.
+    pub fn with_capacity_kib(kib: usize) -> Self {
+        Self::new(kib * 1024)
+    }
+    /// Start a builder for a flash of `mib` MiB.
+    ///
+    /// ```
+    /// use embedded_storage_sim::{SimulatedNorFlash, SimulatedNorFlashBuilder};
+    ///
+    /// let flash: SimulatedNorFlash = SimulatedNorFlashBuilder::with_capacity_mib(2).build();
+    /// assert_eq!(flash.size(), 2 * 1024 * 1024);
+    /// ```
+    pub fn with_capacity_mib(mib: usize) -> Self {
+        Self::with_capacity_kib(mib * 1024)
+    }
     /// Set the maximum number of erase cycles considered "safe" per page.
     /// After this threshold, the simulator may introduce stuck-bit failures.
     pub fn with_minimum_erase_cycles(mut self, cycles: u32) -> Self {
@@ -169,171 +783,1739 @@ impl SimulatedNorFlashBuilder {
         self.bit_failure_every_x_erases = bit_failure_every_x_erases;
         self
     }
+    /// Scale the failure rate per page to model weak sectors.
+    ///
+    /// `weight` is called with the page index during erase failure
+    /// injection. A page with weight `w` gets one stuck bit every
+    /// `failure_rate / w` erase cycles past its limit (at least every cycle),
+    /// so `2.0` doubles the defect rate and `0.0` disables failures for the
+    /// page. Without a weight all pages use the uniform rate. The weight
+    /// does not consume randomness, so runs stay deterministic under a fixed
+    /// seed as long as the closure is.
+    pub fn with_failure_weight(
+        mut self,
+        weight: impl Fn(usize) -> f32 + Send + Sync + 'static,
+    ) -> Self {
+        self.failure_weight = Some(Arc::new(weight));
+        self
+    }
+    /// Let writes to pages past their endurance fail to clear a bit with
+    /// the given probability.
+    ///
+    /// See [`SimpleWearModel::with_program_failure_rate`]. The affected
+    /// bits are counted in [`SimulatedNorFlash::program_failures`].
+    ///
+    /// ```
+    /// use embedded_storage::nor_flash::{NorFlash, ReadNorFlash};
+    /// use embedded_storage_sim::{SimulatedNorFlash, SimulatedNorFlashBuilder};
+    ///
+    /// let mut flash: SimulatedNorFlash = SimulatedNorFlashBuilder::new(4096)
+    ///     .with_minimum_erase_cycles(0)
+    ///     .with_program_failure_rate(1.0)
+    ///     .with_rng_seed(1)
+    ///     .build();
+    /// flash.erase(0, 4096).unwrap();
+    /// flash.write(0, &[0x00; 16]).unwrap();
+    /// assert_eq!(flash.program_failures(), 1);
+    /// let mut bytes = [0; 16];
+    /// flash.read(0, &mut bytes).unwrap();
+    /// assert_eq!(bytes.iter().map(|b| b.count_ones()).sum::<u32>(), 1);
+    /// ```
+    pub fn with_program_failure_rate(mut self, probability: f32) -> Self {
+        self.program_failure_rate = probability;
+        self
+    }
+    /// Replace the default [`SimpleWearModel`] with a custom failure policy.
+    ///
+    /// Every built flash gets its own clone of `model`. The failure rates
+    /// and weight configured on this builder only apply to the default
+    /// model and are ignored when a custom one is set.
+    pub fn with_failure_model(mut self, model: impl FailureModel + Clone + Sync + 'static) -> Self {
+        self.failure_model = Some(Arc::new(move || Box::new(model.clone())));
+        self
+    }
     /// Make failure injection deterministic by fixing the RNG seed.
     pub fn with_rng_seed(mut self, rng_seed: u64) -> Self {
         self.rng_seed = Some(rng_seed);
         self
     }
+    /// Draw a random seed from OS entropy and use it for failure injection.
+    ///
+    /// Returns the chosen seed alongside the builder so it can be saved to
+    /// reproduce the run with [`with_rng_seed`](Self::with_rng_seed). With
+    /// the `log` feature the seed is also logged at info level.
+    #[cfg(feature = "std")]
+    pub fn with_recorded_seed(self) -> (Self, u64) {
+        let seed = os_rng_seed();
+        (self.with_rng_seed(seed), seed)
+    }
     /// Enable transaction logging at the requested granularity.
     pub fn with_logging(mut self, level: TransactionLogLevel) -> Self {
         self.log_level = level;
         self
     }
+    /// Record the tag of every [`start_operation`] call in order.
+    ///
+    /// See [`SimulatedNorFlash::operation_history`] and
+    /// [`SimulatedNorFlash::operation_transaction_ranges`]. Off by default to
+    /// avoid cloning every tag.
+    ///
+    /// [`start_operation`]: SimulatedNorFlash::start_operation
+    pub fn with_operation_history(mut self, operation_history: bool) -> Self {
+        self.operation_history = operation_history;
+        self
+    }
+    /// Model byte-addressable RAM-like memory (e.g. FRAM/MRAM) instead of NOR flash.
+    ///
+    /// The memory starts zero-initialized, writes overwrite bytes directly
+    /// instead of AND-ing them into the current contents, and erases are
+    /// no-ops that leave contents and wear untouched. Erases are still
+    /// logged and counted in the statistics.
+    pub fn with_ram_semantics(mut self, ram_semantics: bool) -> Self {
+        self.ram_semantics = ram_semantics;
+        self
+    }
+    /// Track only statistics and wear, without storing the flash contents.
+    ///
+    /// No memory is allocated for the data or the stuck-bit masks, so very
+    /// large devices can be simulated for timing and wear studies. Contents
+    /// are not tracked: reads always return erased bytes, writes and erases
+    /// only update counters and page cycles, and no stuck bits are injected
+    /// (the wear threshold callback still fires). Logged transactions carry
+    /// the written bytes as `after_write` and no pre-write or pre-erase data.
+    pub fn with_metadata_only(mut self, metadata_only: bool) -> Self {
+        self.metadata_only = metadata_only;
+        self
+    }
+    /// Reject writes that would need to flip a 0 bit back to 1.
+    ///
+    /// NOR flash can only clear bits, so programming a byte that isn't fully
+    /// erased silently yields the AND of old and new value. With strict
+    /// writes enabled, such a `write` fails with
+    /// [`FlashError::WriteToNonErased`] and leaves the contents untouched.
+    /// Has no effect with RAM semantics or without stored contents.
+    pub fn with_strict_writes(mut self, strict_writes: bool) -> Self {
+        self.strict_writes = strict_writes;
+        self
+    }
+    /// Reject writes to bytes already programmed since their last erase.
+    ///
+    /// [`MultiwriteNorFlash`](embedded_storage::nor_flash::MultiwriteNorFlash)
+    /// legitimately programs a word more than once, so this is a debug mode
+    /// for code that assumes single writes: a `write` touching such a byte
+    /// fails with [`FlashError::RewriteWithoutErase`] and leaves the contents
+    /// untouched. Has no effect with RAM semantics.
+    ///
+    /// ```
+    /// use embedded_storage::nor_flash::NorFlash;
+    /// use embedded_storage_sim::{FlashError, SimulatedNorFlash, SimulatedNorFlashBuilder};
+    ///
+    /// let mut flash: SimulatedNorFlash = SimulatedNorFlashBuilder::new(4096)
+    ///     .with_rewrite_detection(true)
+    ///     .build();
+    /// flash.write(0, &[0xff, 0x0f]).unwrap();
+    /// assert_eq!(
+    ///     flash.write(1, &[0x00]),
+    ///     Err(FlashError::RewriteWithoutErase { offset: 1 })
+    /// );
+    /// flash.erase(0, 4096).unwrap();
+    /// flash.write(1, &[0x00]).unwrap();
+    /// ```
+    pub fn with_rewrite_detection(mut self, rewrite_detection: bool) -> Self {
+        self.rewrite_detection = rewrite_detection;
+        self
+    }
+    /// Maintain a CRC-32 per page, see [`SimulatedNorFlash::sector_crcs`].
+    ///
+    /// Every write and erase recomputes the CRCs of the pages it touches,
+    /// so this is off by default. Has no effect without stored contents.
+    pub fn with_sector_crcs(mut self, sector_crcs: bool) -> Self {
+        self.sector_crcs = sector_crcs;
+        self
+    }
+    /// Skip erasing pages that are already blank (all `0xFF`).
+    ///
+    /// Models controllers that detect blank pages: a skipped page neither
+    /// wears nor counts towards `pages_erased`, and an erase that only
+    /// covers blank pages doesn't count as an erase access either, so it
+    /// costs no erase time. Pages with stuck-at-0 bits never read as blank.
+    /// Without stored contents every page is erased.
+    ///
+    /// ```
+    /// use embedded_storage::nor_flash::NorFlash;
+    /// use embedded_storage_sim::{SimulatedNorFlash, SimulatedNorFlashBuilder};
+    ///
+    /// let mut flash: SimulatedNorFlash = SimulatedNorFlashBuilder::new(2 * 4096)
+    ///     .with_skip_erased_pages(true)
+    ///     .build();
+    /// flash.write(4096, &[0x00]).unwrap();
+    /// flash.erase(0, 2 * 4096).unwrap();
+    /// assert_eq!(flash.page_erase_cycles(), [0, 1]);
+    /// flash.erase(0, 2 * 4096).unwrap();
+    /// assert_eq!(flash.page_erase_cycles(), [0, 1]);
+    /// assert_eq!(flash.stats().erase_accesses, 1);
+    /// ```
+    pub fn with_skip_erased_pages(mut self, skip_erased_pages: bool) -> Self {
+        self.skip_erased_pages = skip_erased_pages;
+        self
+    }
+    /// Let each page of an erase fail with the given probability.
+    ///
+    /// Models a brownout during a multi-sector erase: pages are erased in
+    /// order, and the first failing page aborts the command with
+    /// [`FlashError::EraseFailed`]. The pages before it are erased, worn and
+    /// counted as usual; the failing page and everything after it keep their
+    /// contents and cycle counts. Blank pages skipped with
+    /// [`with_skip_erased_pages`](Self::with_skip_erased_pages) never fail.
+    /// The failures draw from the seeded RNG, so they are reproducible.
+    ///
+    /// ```
+    /// use embedded_storage::nor_flash::NorFlash;
+    /// use embedded_storage_sim::{FlashError, SimulatedNorFlash, SimulatedNorFlashBuilder};
+    ///
+    /// let mut flash: SimulatedNorFlash = SimulatedNorFlashBuilder::new(8 * 4096)
+    ///     .with_erase_failure_rate(0.25)
+    ///     .with_rng_seed(4)
+    ///     .build();
+    /// flash.write(0, &[0x00; 8 * 4096]).unwrap();
+    /// assert_eq!(
+    ///     flash.erase(0, 8 * 4096),
+    ///     Err(FlashError::EraseFailed { completed_pages: 4 })
+    /// );
+    /// assert_eq!(flash.pages_erased(), 4);
+    /// assert_eq!(flash.page_erase_cycles(), [1, 1, 1, 1, 0, 0, 0, 0]);
+    /// assert_eq!(flash.data()[4 * 4096 - 1], 0xff);
+    /// assert_eq!(flash.data()[4 * 4096], 0x00);
+    /// ```
+    pub fn with_erase_failure_rate(mut self, probability: f32) -> Self {
+        self.erase_failure_rate = probability;
+        self
+    }
+    /// Return `pattern` instead of the erased value when reading bytes that
+    /// weren't written since the last erase.
+    ///
+    /// Catches code that reads before writing. Written-ness is tracked per
+    /// byte: a write marks its range, an erase clears it again (except with
+    /// RAM semantics), and [`reset_to`](SimulatedNorFlash::reset_to) marks the
+    /// whole restored image as written. The pattern repeats from offset 0,
+    /// so byte `i` reads as `pattern[i % pattern.len()]`, and it replaces
+    /// stuck bits as well. Only reads are affected: [`data`](SimulatedNorFlash::data)
+    /// and [`effective_byte`](SimulatedNorFlash::effective_byte) still show
+    /// the erased contents.
+    ///
+    /// [`build`](Self::build) panics if `pattern` is empty.
+    ///
+    /// ```
+    /// use embedded_storage::nor_flash::{NorFlash, ReadNorFlash};
+    /// use embedded_storage_sim::{SimulatedNorFlash, SimulatedNorFlashBuilder};
+    ///
+    /// let mut flash: SimulatedNorFlash = SimulatedNorFlashBuilder::new(4096)
+    ///     .with_uninitialized_poison(&[0xde, 0xad])
+    ///     .build();
+    /// flash.write(1, &[0x42, 0x43]).unwrap();
+    /// let mut bytes = [0; 4];
+    /// flash.read(0, &mut bytes).unwrap();
+    /// assert_eq!(bytes, [0xde, 0x42, 0x43, 0xad]);
+    /// flash.erase(0, 4096).unwrap();
+    /// flash.read(0, &mut bytes).unwrap();
+    /// assert_eq!(bytes, [0xde, 0xad, 0xde, 0xad]);
+    /// ```
+    pub fn with_uninitialized_poison(mut self, pattern: &[u8]) -> Self {
+        self.uninitialized_poison = Some(pattern.to_vec());
+        self
+    }
+    /// Read back every write and fail with [`FlashError::VerifyFailed`] if
+    /// the data differs.
+    ///
+    /// Models drivers that verify after programming: the read back counts
+    /// towards the read stats and is logged like any other read, and stuck
+    /// bits or failed programs surface at write time. Multiwrites are
+    /// compared against the AND of old and new data. Without stored
+    /// contents the read is counted but never fails.
+    ///
+    /// ```
+    /// use embedded_storage::nor_flash::NorFlash;
+    /// use embedded_storage_sim::{
+    ///     Defect, ErasedPage, FailureKind, FailureModel, FlashError, SimulatedNorFlash,
+    ///     SimulatedNorFlashBuilder, Transaction, TransactionLogLevel,
+    /// };
+    /// use rand::RngCore;
+    ///
+    /// #[derive(Clone)]
+    /// struct StuckAtOffset3;
+    ///
+    /// impl FailureModel for StuckAtOffset3 {
+    ///     fn on_erase(&mut self, _: &ErasedPage, _: &mut dyn RngCore) -> Vec<Defect> {
+    ///         vec![Defect { offset: 3, bit: 0, kind: FailureKind::StuckAt1 }]
+    ///     }
+    /// }
+    ///
+    /// let mut flash: SimulatedNorFlash = SimulatedNorFlashBuilder::new(4096)
+    ///     .with_write_verify(true)
+    ///     .with_failure_model(StuckAtOffset3)
+    ///     .with_logging(TransactionLogLevel::Minimal)
+    ///     .build();
+    /// flash.write(0, &[0x0f; 16]).unwrap();
+    /// assert_eq!(flash.bytes_read(), 16);
+    ///
+    /// flash.erase(0, 4096).unwrap();
+    /// let reason = FlashError::VerifyFailed { offset: 3 };
+    /// assert_eq!(flash.write(0, &[0x00; 16]), Err(reason));
+    /// assert!(matches!(
+    ///     flash.transactions().last(),
+    ///     Some(&Transaction::Rejected { offset: 0, reason: r, .. }) if r == reason
+    /// ));
+    /// ```
+    pub fn with_write_verify(mut self, write_verify: bool) -> Self {
+        self.write_verify = write_verify;
+        self
+    }
+    /// Let programmed bits decay over time, see
+    /// [`SimulatedNorFlash::advance_time`].
+    #[cfg(feature = "retention")]
+    pub fn with_retention(mut self, model: RetentionModel) -> Self {
+        self.retention = Some(model);
+        self
+    }
+    /// Enable [`erase_subsector`](SimulatedNorFlash::erase_subsector) with
+    /// sub-sectors of `subsector_size` bytes.
+    ///
+    /// Models parts that erase both 64 KiB sectors (`ERASE_SIZE`) and 4 KiB
+    /// sub-sectors. [`build`](Self::build) panics if `subsector_size` is zero
+    /// or doesn't divide `ERASE_SIZE`.
+    pub fn with_subsector_size(mut self, subsector_size: usize) -> Self {
+        self.subsector_size = Some(subsector_size);
+        self
+    }
+    /// Set the internal programming granularity in bytes.
+    ///
+    /// Defaults to `WRITE_SIZE`. Some parts program in units smaller than
+    /// the caller-facing write alignment; every unit touched by a write is
+    /// counted as a partial program cycle in
+    /// [`FlashStats::program_units_written`]. This models the cost of many
+    /// small multiwrites within a page. Alignment checks still use
+    /// `WRITE_SIZE`.
+    ///
+    /// [`build`](Self::build) panics if `program_unit` is zero.
+    pub fn with_program_unit(mut self, program_unit: usize) -> Self {
+        self.program_unit = Some(program_unit);
+        self
+    }
+    /// Count read and write accesses per page or block touched instead of
+    /// per call.
+    ///
+    /// A large read or write may need several bus commands on the real
+    /// device, e.g. one per 256 byte program page. Counting those in
+    /// `read_accesses` and `write_accesses` makes the per-access overhead in
+    /// [`FlashTimings`] more realistic. Erase accesses are always counted
+    /// per call. [`build`](Self::build) panics on a zero block size.
+    ///
+    /// ```
+    /// use embedded_storage::nor_flash::NorFlash;
+    /// use embedded_storage_sim::{
+    ///     AccessGranularity, FlashTimings, SimulatedNorFlash, SimulatedNorFlashBuilder, SpiType,
+    ///     TransactionLogLevel,
+    /// };
+    /// use fugit::{ExtU64, RateExtU64};
+    ///
+    /// let mut flash: SimulatedNorFlash = SimulatedNorFlashBuilder::new(4096)
+    ///     .with_access_granularity(AccessGranularity::PerBlock(256))
+    ///     .with_logging(TransactionLogLevel::Minimal)
+    ///     .build();
+    /// flash.write(200, &[0; 600]).unwrap();
+    /// assert_eq!(flash.stats().write_accesses, 4);
+    ///
+    /// // the per-entry timings count the same accesses
+    /// let timings = FlashTimings::new(SpiType::QSPI, false, 100.MHz(), 45.millis(), 40);
+    /// let entries = flash.transactions_with_timings(&timings);
+    /// assert_eq!(entries[0].1, timings.write_time(600, 4));
+    /// ```
+    pub fn with_access_granularity(mut self, access_granularity: AccessGranularity) -> Self {
+        self.access_granularity = access_granularity;
+        self
+    }
+    /// Model a controller read cache of `lines` lines of `line_size` bytes.
+    ///
+    /// A read whose lines are all cached is served without a bus access:
+    /// it counts as a [`read_cache_hits`](SimulatedNorFlash::read_cache_hits)
+    /// instead of towards `bytes_read` and `read_accesses`, so it costs no
+    /// read time. Any other read counts as usual and caches its lines,
+    /// evicting the least recently used ones. [`build`](Self::build) panics
+    /// if either argument is zero.
+    ///
+    /// The cache only models timing: it tracks which lines are cached, not
+    /// their contents, so a hit still returns the current contents and
+    /// never stale data. Every change to the contents invalidates the lines
+    /// it touches, whether by a write or erase or by
+    /// [`corrupt_bits`](SimulatedNorFlash::corrupt_bits),
+    /// [`apply_defect_map`](SimulatedNorFlash::apply_defect_map), retention
+    /// loss or an undo.
+    ///
+    /// ```
+    /// use embedded_storage::nor_flash::{NorFlash, ReadNorFlash};
+    /// use embedded_storage_sim::{SimulatedNorFlash, SimulatedNorFlashBuilder};
+    ///
+    /// let mut flash: SimulatedNorFlash = SimulatedNorFlashBuilder::new(4096)
+    ///     .with_read_cache(64, 4)
+    ///     .build();
+    /// let mut buf = [0; 16];
+    /// flash.read(0, &mut buf).unwrap();
+    /// flash.read(16, &mut buf).unwrap();
+    /// flash.write(32, &[0; 4]).unwrap();
+    /// flash.read(0, &mut buf).unwrap();
+    /// assert_eq!((flash.read_cache_hits(), flash.read_cache_misses()), (1, 2));
+    /// assert_eq!(flash.stats().read_accesses, 2);
+    ///
+    /// flash.corrupt_bits(0..16, 3);
+    /// flash.read(0, &mut buf).unwrap();
+    /// assert_eq!((flash.read_cache_hits(), flash.read_cache_misses()), (1, 3));
+    /// ```
+    pub fn with_read_cache(mut self, line_size: usize, lines: usize) -> Self {
+        self.read_cache = Some((line_size, lines));
+        self
+    }
+    /// Reserve `range` as a hole in the memory map.
+    ///
+    /// Any read, write or erase touching it fails with
+    /// [`FlashError::Unmapped`] without accessing the flash, while accesses
+    /// right next to it work as usual. Can be called repeatedly to add
+    /// several holes.
+    ///
+    /// ```
+    /// use embedded_storage::nor_flash::{NorFlash, ReadNorFlash};
+    /// use embedded_storage_sim::{FlashError, SimulatedNorFlash, SimulatedNorFlashBuilder};
+    ///
+    /// let mut flash: SimulatedNorFlash = SimulatedNorFlashBuilder::new(4 * 4096)
+    ///     .with_unmapped_region(4096..2 * 4096)
+    ///     .build();
+    /// let mut buf = [0; 16];
+    /// flash.read(4096 - 16, &mut buf).unwrap();
+    /// flash.read(2 * 4096, &mut buf).unwrap();
+    /// assert_eq!(flash.read(4096 - 8, &mut buf), Err(FlashError::Unmapped { offset: 4096 }));
+    /// assert_eq!(flash.write(4100, &buf), Err(FlashError::Unmapped { offset: 4100 }));
+    /// assert_eq!(flash.erase(0, 4 * 4096), Err(FlashError::Unmapped { offset: 4096 }));
+    /// assert_eq!(flash.bytes_read(), 32);
+    /// ```
+    pub fn with_unmapped_region(mut self, range: Range<u32>) -> Self {
+        self.unmapped_regions.push(range);
+        self
+    }
+    /// Start from an already aged device with the given per-page erase cycles.
+    ///
+    /// Subsequent erases continue counting from these values and may inject
+    /// failures right away. The length must match the page count; this is
+    /// checked in [`build`](Self::build).
+    ///
+    /// ```
+    /// use embedded_storage::nor_flash::NorFlash;
+    /// use embedded_storage_sim::{SimulatedNorFlash, SimulatedNorFlashBuilder};
+    ///
+    /// let mut flash: SimulatedNorFlash = SimulatedNorFlashBuilder::new(2 * 4096)
+    ///     .with_minimum_erase_cycles(100)
+    ///     .with_failure_rate(1)
+    ///     .with_initial_page_cycles(vec![100, 0])
+    ///     .build();
+    /// flash.erase(0, 2 * 4096).unwrap();
+    /// assert_eq!(flash.page_erase_cycles(), [101, 1]);
+    /// // only the aged page is past its endurance
+    /// assert_eq!(flash.pages_with_defects(), [0]);
+    ///
+    /// let wrong_length = std::panic::catch_unwind(|| {
+    ///     let _: SimulatedNorFlash = SimulatedNorFlashBuilder::new(2 * 4096)
+    ///         .with_initial_page_cycles(vec![100])
+    ///         .build();
+    /// });
+    /// assert!(wrong_length.is_err());
+    /// ```
+    pub fn with_initial_page_cycles(mut self, page_cycles: Vec<u32>) -> Self {
+        self.initial_page_cycles = Some(page_cycles);
+        self
+    }
     /// Build a [`SimulatedNorFlash`] with the chosen `READ_SIZE`, `WRITE_SIZE`, and `ERASE_SIZE`.
     pub fn build<O: Clone, const RS: usize, const WS: usize, const ES: usize>(
         &self,
     ) -> SimulatedNorFlash<O, RS, WS, ES> {
-        let mut flash = SimulatedNorFlash::new_with_failures(
-            self.size,
+        let mut flash = SimulatedNorFlash::allocate(self.size, self.metadata_only).with_failures(
             self.minimum_erase_cycles,
             self.bit_failure_every_x_erases,
             self.rng_seed,
         );
         flash.set_logging(self.log_level);
+        flash.record_operation_history = self.operation_history;
+        flash.ram_semantics = self.ram_semantics;
+        flash.strict_writes = self.strict_writes;
+        if self.rewrite_detection && !self.ram_semantics {
+            flash.programmed = vec![false; self.size];
+        }
+        flash.skip_erased_pages = self.skip_erased_pages;
+        flash.erase_failure_rate = self.erase_failure_rate;
+        flash.write_verify = self.write_verify;
+        flash.unmapped_regions = self.unmapped_regions.clone();
+        #[cfg(feature = "retention")]
+        {
+            flash.retention = self.retention;
+        }
+        if let Some(subsector_size) = self.subsector_size {
+            assert!(subsector_size > 0 && ES.is_multiple_of(subsector_size));
+            flash.subsector_size = subsector_size;
+            flash.subsector_cycles = vec![0; self.size / subsector_size];
+        }
+        if let Some(pattern) = &self.uninitialized_poison {
+            assert!(!pattern.is_empty());
+            flash.initialized = vec![false; self.size];
+            flash.uninitialized_poison = Some(pattern.clone());
+        }
+        flash.failure_model = match &self.failure_model {
+            Some(factory) => factory(),
+            None => Box::new(
+                SimpleWearModel::new(self.bit_failure_every_x_erases)
+                    .with_shared_weight(self.failure_weight.clone())
+                    .with_program_failure_rate(self.program_failure_rate),
+            ),
+        };
+        assert_ne!(self.access_granularity, AccessGranularity::PerBlock(0));
+        flash.access_granularity = self.access_granularity;
+        if let Some((line_size, lines)) = self.read_cache {
+            assert!(line_size > 0 && lines > 0);
+            flash.read_cache = Some(ReadCache {
+                line_size,
+                lines,
+                cached: Vec::with_capacity(lines),
+            });
+        }
+        if let Some(program_unit) = self.program_unit {
+            assert!(program_unit > 0);
+            flash.program_unit = program_unit;
+        }
+        let initial_value = flash.initial_value();
+        flash.data.fill(initial_value);
+        if let Some(page_cycles) = &self.initial_page_cycles {
+            flash.set_page_cycles(page_cycles);
+        }
+        if self.sector_crcs && !self.metadata_only {
+            flash.sector_crcs = vec![0; self.size / ES];
+            flash.update_sector_crcs(0..self.size);
+        }
         flash
     }
 }
 
+/// Draw a single seed from the OS RNG.
+#[cfg(feature = "std")]
+fn os_rng_seed() -> u64 {
+    use rand::TryRngCore;
+    let seed = rand::rngs::OsRng
+        .try_next_u64()
+        .expect("OS RNG should be available");
+    #[cfg(feature = "log")]
+    log::info!("drew RNG seed {seed} from OS entropy");
+    seed
+}
+
+/// CRC-32 (IEEE 802.3) lookup table for [`crc32`].
+const CRC32_TABLE: [u32; 256] = {
+    let mut table = [0; 256];
+    let mut i = 0;
+    while i < 256 {
+        let mut crc = i as u32;
+        let mut bit = 0;
+        while bit < 8 {
+            crc = if crc & 1 != 0 {
+                crc >> 1 ^ 0xedb8_8320
+            } else {
+                crc >> 1
+            };
+            bit += 1;
+        }
+        table[i] = crc;
+        i += 1;
+    }
+    table
+};
+
+/// CRC-32 (IEEE 802.3) of `bytes`, as used by zlib and Ethernet.
+fn crc32(bytes: &[u8]) -> u32 {
+    !bytes.iter().fold(!0, |crc, &byte| {
+        CRC32_TABLE[((crc ^ byte as u32) & 0xff) as usize] ^ crc >> 8
+    })
+}
+
+/// Writer registered with [`SimulatedNorFlash::set_jsonl_writer`] that
+/// transactions are streamed to instead of kept in the log.
+#[cfg(feature = "std")]
+struct TransactionSink<O> {
+    writer: Box<dyn std::io::Write + Send>,
+    to_json: fn(&Transaction<O>) -> String,
+}
+
+/// Periodic snapshot callback registered with
+/// [`SimulatedNorFlash::set_snapshot_interval`].
+///
+/// `snapshot` is captured at registration, where `O: ToString` is known.
+struct SnapshotSampler<F> {
+    interval: usize,
+    snapshot: fn(&F) -> FlashSnapshot,
+    callback: Box<dyn FnMut(FlashSnapshot) + Send>,
+}
+
+/// Controller read cache, see [`SimulatedNorFlashBuilder::with_read_cache`].
+struct ReadCache {
+    line_size: usize,
+    lines: usize,
+    /// Indices of the cached lines, least recently used first.
+    cached: Vec<usize>,
+}
+
+/// Callback registered with [`SimulatedNorFlash::set_low_space_callback`].
+struct LowSpaceMonitor {
+    threshold: usize,
+    measure: FreeSpace,
+    /// Whether free space was below `threshold` at the last check.
+    below: bool,
+    callback: Box<dyn FnMut() + Send>,
+}
+
+/// Everything an operation tag needs for per-operation stats and snapshots.
+///
+/// Implemented for every `Clone + ToString + Eq + Hash` type, so a storage
+/// layer can bring its own taxonomy of operations and use this as the single
+/// bound in generic code. Deriving the traits and implementing [`Display`]
+/// is all it takes:
+///
+/// ```
+/// use core::fmt;
+///
+/// use embedded_storage::nor_flash::NorFlash;
+/// use embedded_storage_sim::{OperationTag, SimulatedNorFlash};
+///
+/// #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+/// enum LogOp {
+///     Append,
+///     Compact,
+/// }
+///
+/// impl fmt::Display for LogOp {
+///     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+///         f.write_str(match self {
+///             LogOp::Append => "append",
+///             LogOp::Compact => "compact",
+///         })
+///     }
+/// }
+///
+/// fn append_all<O: OperationTag>(flash: &mut SimulatedNorFlash<O>, tag: O, count: u32) {
+///     for i in 0..count {
+///         flash.operation(tag.clone()).write(i * 4, &[0; 4]).unwrap();
+///     }
+/// }
+///
+/// let mut flash: SimulatedNorFlash<LogOp> = SimulatedNorFlash::new(4096);
+/// append_all(&mut flash, LogOp::Append, 8);
+/// flash.start_operation(LogOp::Compact);
+/// flash.erase(0, 4096).unwrap();
+///
+/// assert_eq!(flash.stats_by_operation()[&LogOp::Append].bytes_written, 32);
+/// assert_eq!(flash.snapshot(false).last_operation.as_deref(), Some("compact"));
+/// ```
+pub trait OperationTag: Clone + ToString + Eq + Hash {}
+
+impl<T: Clone + ToString + Eq + Hash> OperationTag for T {}
+
 /// In-memory NOR flash that implements the embedded storage traits.
 ///
 /// Type parameters:
-/// - `O`: Optional user-defined "operation" tag type stored alongside transactions.
+/// - `O`: Optional user-defined "operation" tag type stored alongside
+///   transactions, see [`OperationTag`].
 /// - `READ_SIZE`: Minimum alignment for reads in bytes.
 /// - `WRITE_SIZE`: Minimum alignment for writes in bytes.
 /// - `ERASE_SIZE`: Erase unit size in bytes (also the page size for wear tracking).
+///
+/// Zero-length operations (`read(offset, &mut [])`, `write(offset, &[])` and
+/// `erase(x, x)`) are accepted as no-ops: they leave contents, wear and
+/// statistics untouched, but are still recorded as zero-length transactions
+/// when logging is enabled:
+///
+/// ```
+/// use embedded_storage::nor_flash::{NorFlash, ReadNorFlash};
+/// use embedded_storage_sim::{
+///     SimulatedNorFlash, SimulatedNorFlashBuilder, Transaction, TransactionLogLevel,
+/// };
+///
+/// let mut flash: SimulatedNorFlash = SimulatedNorFlashBuilder::new(2 * 4096)
+///     .with_logging(TransactionLogLevel::WriteDataOnly)
+///     .build();
+/// flash.read(16, &mut []).unwrap();
+/// flash.write(32, &[]).unwrap();
+/// flash.erase(4096, 4096).unwrap();
+///
+/// assert_eq!(flash.total_accesses(), 0);
+/// assert_eq!(flash.page_erase_cycles(), [0, 0]);
+/// assert!(matches!(
+///     flash.transactions(),
+///     [
+///         Transaction::Read { offset: 16, length: 0, .. },
+///         Transaction::Write { offset: 32, length: 0, .. },
+///         Transaction::Erase { from: 4096, to: 4096, .. },
+///     ]
+/// ));
+/// ```
+///
+/// Accesses are checked before they touch the flash: out-of-bounds ranges,
+/// offsets or lengths that aren't a multiple of the access size, and
+/// [unmapped regions](SimulatedNorFlashBuilder::with_unmapped_region) are
+/// rejected with an error and recorded as a [`Transaction::Rejected`]:
+///
+/// ```
+/// use embedded_storage::nor_flash::{NorFlash, NorFlashError, NorFlashErrorKind, ReadNorFlash};
+/// use embedded_storage_sim::{BankedFlash, FlashError, SimulatedNorFlash, SimulatedNorFlashBuilder};
+///
+/// let mut flash: SimulatedNorFlash<(), 4, 4, 4096> = SimulatedNorFlashBuilder::new(4 * 4096)
+///     .with_unmapped_region(4096..2 * 4096)
+///     .build();
+/// let mut buf = [0; 8];
+/// assert_eq!(flash.read(4 * 4096 - 4, &mut buf), Err(FlashError::OutOfBounds { offset: 4 * 4096 }));
+/// assert_eq!(flash.read(2, &mut buf), Err(FlashError::NotAligned { offset: 2 }));
+/// assert_eq!(flash.read(4096, &mut buf), Err(FlashError::Unmapped { offset: 4096 }));
+/// assert_eq!(flash.write(8, &[0; 6]), Err(FlashError::NotAligned { offset: 14 }));
+/// assert_eq!(flash.write(5 * 4096, &[0; 4]), Err(FlashError::OutOfBounds { offset: 5 * 4096 }));
+/// assert_eq!(flash.erase(4096, 0), Err(FlashError::OutOfBounds { offset: 4096 }));
+/// assert_eq!(flash.erase(0, 100), Err(FlashError::NotAligned { offset: 100 }));
+/// assert_eq!(flash.erase(0, 2 * 4096), Err(FlashError::Unmapped { offset: 4096 }));
+/// assert_eq!(FlashError::NotAligned { offset: 2 }.kind(), NorFlashErrorKind::NotAligned);
+/// assert_eq!(flash.total_accesses(), 0);
+///
+/// // banked flashes check the whole range before splitting it
+/// let banks: Vec<SimulatedNorFlash> = (0..2).map(|_| SimulatedNorFlash::new(4096)).collect();
+/// let mut banked = BankedFlash::new(banks, 4096);
+/// assert_eq!(banked.read(8190, &mut buf), Err(FlashError::OutOfBounds { offset: 8192 }));
+/// assert_eq!(banked.write(8190, &[0; 8]), Err(FlashError::OutOfBounds { offset: 8192 }));
+/// assert_eq!(banked.erase(4096, 0), Err(FlashError::OutOfBounds { offset: 4096 }));
+/// assert_eq!(banked.erase(0, 3 * 4096), Err(FlashError::OutOfBounds { offset: 8192 }));
+/// assert_eq!(banked.stats().read_accesses, 0);
+/// ```
 pub struct SimulatedNorFlash<
     O = (),
     const READ_SIZE: usize = 1,
     const WRITE_SIZE: usize = 1,
     const ERASE_SIZE: usize = 4096,
 > {
+    size: usize,
+    metadata_only: bool,
     data: Vec<u8>,
     stuck_at_1_bits: Vec<u8>,
     stuck_at_0_bits: Vec<u8>,
     page_cycles: Vec<u32>,
+    /// Sub-sector size in bytes, `0` without sub-sector erase support.
+    subsector_size: usize,
+    subsector_cycles: Vec<u32>,
+    page_endurance: Vec<Option<u32>>,
+    /// `total_operations` at each page's last erase.
+    page_last_erased_op: Vec<Option<usize>>,
     read: usize,
     written: usize,
-    erased: usize,
+    written_extent: Option<Range<u32>>,
+    writes_setting_bits: usize,
+    corrupt_reads: usize,
+    padding_bytes: usize,
+    program_failures: usize,
+    calibration: Calibration,
+    program_units_written: usize,
+    erased_pages: usize,
+    erased_subsectors: usize,
     read_accesses: usize,
     write_accesses: usize,
     erase_accesses: usize,
     total_operations: usize,
+    record_operation_history: bool,
+    operation_history: Vec<O>,
+    /// Transaction log indices of each entry in `operation_history`.
+    operation_transactions: Vec<Range<usize>>,
     log_level: TransactionLogLevel,
     transactions: Vec<Transaction<O>>,
-    rng: rand::rngs::SmallRng,
+    /// Number of transactions cleared from the log, so cursors stay valid.
+    transactions_dropped: usize,
+    /// Cursor positions given up by `undo_last`: from log index `.0` on,
+    /// cursors are `.1` ahead of `transactions_dropped + index`.
+    transactions_undone: Vec<(usize, usize)>,
+    rng: FailureRng,
+    rng_seed: u64,
     minimum_safe_erase_cycles: u32,
-    bit_failure_every_x_erases: u32,
+    failure_model: Box<dyn FailureModel>,
+    ram_semantics: bool,
+    strict_writes: bool,
+    skip_erased_pages: bool,
+    erase_failure_rate: f32,
+    uninitialized_poison: Option<Vec<u8>>,
+    write_verify: bool,
+    #[cfg(feature = "retention")]
+    retention: Option<RetentionModel>,
+    /// Reserved ranges that reject every access.
+    unmapped_regions: Vec<Range<u32>>,
+    /// Per byte whether it was written since the last erase, only tracked
+    /// with an `uninitialized_poison` pattern.
+    initialized: Vec<bool>,
+    /// Bytes written since their last erase, only tracked with rewrite
+    /// detection enabled.
+    programmed: Vec<bool>,
+    /// CRC-32 of every page's stored contents, only maintained with
+    /// `with_sector_crcs`.
+    sector_crcs: Vec<u32>,
+    program_unit: usize,
+    access_granularity: AccessGranularity,
+    read_cache: Option<ReadCache>,
+    read_cache_hits: usize,
+    read_cache_misses: usize,
     current_operation: Option<O>,
-    last_operation_stats: FlashStats,
+    operation_start_stats: FlashStats,
+    operation_stats: Vec<(O, FlashStats)>,
+    #[cfg(feature = "std")]
+    transaction_sink: Option<TransactionSink<O>>,
+    #[cfg(feature = "std")]
+    transaction_sink_errors: usize,
+    wear_threshold_callback: Option<Box<dyn FnMut(usize, u32) + Send>>,
+    failure_callback: Option<Box<dyn FnMut(FailureEvent) + Send>>,
+    corrupt_read_callback: Option<Box<dyn FnMut(u32) + Send>>,
+    snapshot_sampler: Option<SnapshotSampler<Self>>,
+    low_space_monitor: Option<LowSpaceMonitor>,
+    #[cfg(feature = "std")]
+    realtime_playback: Option<(FlashTimings, f32)>,
 }
 
-impl<O: Clone, const RS: usize, const WS: usize, const ES: usize> SimulatedNorFlash<O, RS, WS, ES> {
-    /// Create an erased flash (all bits set to 1) of `size` bytes.
+impl<O, const RS: usize, const WS: usize, const ES: usize> SimulatedNorFlash<O, RS, WS, ES> {
+    /// Round `offset` up to the next `WRITE_SIZE` boundary, or
+    /// [`FlashError::OutOfBounds`] if that is past `u32::MAX`.
     ///
-    /// Panics if `size` is not a multiple of `ERASE_SIZE`.
-    pub fn new(size: usize) -> Self {
+    /// ```
+    /// use embedded_storage_sim::{FlashError, SimulatedNorFlashR1W4E4k};
+    ///
+    /// type Flash = SimulatedNorFlashR1W4E4k<()>;
+    /// assert_eq!(Flash::align_up_write(5), Ok(8));
+    /// assert_eq!(Flash::align_up_write(u32::MAX), Err(FlashError::OutOfBounds { offset: u32::MAX }));
+    /// ```
+    pub const fn align_up_write(offset: u32) -> Result<u32, FlashError> {
+        match offset.checked_next_multiple_of(WS as u32) {
+            Some(aligned) => Ok(aligned),
+            None => Err(FlashError::OutOfBounds { offset }),
+        }
+    }
+    /// Round `offset` down to the previous `WRITE_SIZE` boundary.
+    ///
+    /// ```
+    /// use embedded_storage_sim::SimulatedNorFlashR1W4E4k;
+    ///
+    /// assert_eq!(SimulatedNorFlashR1W4E4k::<()>::align_down_write(5), 4);
+    /// ```
+    pub const fn align_down_write(offset: u32) -> u32 {
+        offset / WS as u32 * WS as u32
+    }
+    /// Round `offset` up to the next `ERASE_SIZE` boundary, or
+    /// [`FlashError::OutOfBounds`] if that is past `u32::MAX`.
+    ///
+    /// ```
+    /// use embedded_storage_sim::{FlashError, SimulatedNorFlashR1W4E4k};
+    ///
+    /// type Flash = SimulatedNorFlashR1W4E4k<()>;
+    /// assert_eq!(Flash::align_up_erase(4097), Ok(8192));
+    /// assert_eq!(Flash::align_up_erase(u32::MAX - 4094), Err(FlashError::OutOfBounds { offset: u32::MAX - 4094 }));
+    /// ```
+    pub const fn align_up_erase(offset: u32) -> Result<u32, FlashError> {
+        match offset.checked_next_multiple_of(ES as u32) {
+            Some(aligned) => Ok(aligned),
+            None => Err(FlashError::OutOfBounds { offset }),
+        }
+    }
+    /// Round `offset` down to the previous `ERASE_SIZE` boundary.
+    ///
+    /// ```
+    /// use embedded_storage_sim::SimulatedNorFlashR1W4E4k;
+    ///
+    /// assert_eq!(SimulatedNorFlashR1W4E4k::<()>::align_down_erase(4097), 4096);
+    /// ```
+    pub const fn align_down_erase(offset: u32) -> u32 {
+        offset / ES as u32 * ES as u32
+    }
+    /// Whether `offset` (or a length) is a multiple of `WRITE_SIZE`.
+    ///
+    /// ```
+    /// use embedded_storage_sim::SimulatedNorFlashR1W4E4k;
+    ///
+    /// assert!(SimulatedNorFlashR1W4E4k::<()>::is_write_aligned(8));
+    /// assert!(!SimulatedNorFlashR1W4E4k::<()>::is_write_aligned(6));
+    /// ```
+    pub const fn is_write_aligned(offset: u32) -> bool {
+        offset.is_multiple_of(WS as u32)
+    }
+    /// Whether `offset` (or a length) is a multiple of `ERASE_SIZE`.
+    ///
+    /// ```
+    /// use embedded_storage_sim::SimulatedNorFlashR1W4E4k;
+    ///
+    /// assert!(SimulatedNorFlashR1W4E4k::<()>::is_erase_aligned(8192));
+    /// assert!(!SimulatedNorFlashR1W4E4k::<()>::is_erase_aligned(100));
+    /// ```
+    pub const fn is_erase_aligned(offset: u32) -> bool {
+        offset.is_multiple_of(ES as u32)
+    }
+    /// Padding needed to round a write of `len` bytes up to `WRITE_SIZE`.
+    ///
+    /// ```
+    /// use embedded_storage_sim::SimulatedNorFlashR1W4E4k;
+    ///
+    /// assert_eq!(SimulatedNorFlashR1W4E4k::<()>::padding_for(5), 3);
+    /// assert_eq!(SimulatedNorFlashR1W4E4k::<()>::padding_for(8), 0);
+    /// ```
+    pub const fn padding_for(len: usize) -> usize {
+        len.div_ceil(WS) * WS - len
+    }
+}
+
+impl<O: Clone, const RS: usize, const WS: usize, const ES: usize> SimulatedNorFlash<O, RS, WS, ES> {
+    /// Create an erased flash (all bits set to 1) of `size` bytes.
+    ///
+    /// `ERASE_SIZE` doesn't have to be a power of two; all page math uses
+    /// division and modulo:
+    ///
+    /// ```
+    /// use embedded_storage::nor_flash::NorFlash;
+    /// use embedded_storage_sim::SimulatedNorFlash;
+    ///
+    /// let mut flash = SimulatedNorFlash::<(), 1, 1, 3000>::new(3 * 3000);
+    /// flash.erase(3000, 9000).unwrap();
+    /// flash.erase(6000, 9000).unwrap();
+    /// assert_eq!(flash.page_count(), 3);
+    /// assert_eq!(flash.page_erase_cycles(), [0, 1, 2]);
+    /// assert_eq!(flash.pages_erased(), 3);
+    /// ```
+    ///
+    /// Panics if `size` is not a multiple of `ERASE_SIZE`.
+    pub fn new(size: usize) -> Self {
+        Self::allocate(size, false)
+    }
+    /// Create a flash that uses `data` as its contents, without copying it.
+    ///
+    /// Useful for large firmware images that are already in memory. The
+    /// flash starts without wear or stuck bits, like [`new`](Self::new).
+    ///
+    /// ```
+    /// use embedded_storage::nor_flash::ReadNorFlash;
+    /// use embedded_storage_sim::SimulatedNorFlash;
+    ///
+    /// let mut image = vec![0xff; 2 * 4096];
+    /// image[..4].copy_from_slice(b"boot");
+    /// let mut flash: SimulatedNorFlash = SimulatedNorFlash::from_vec(image);
+    /// let mut magic = [0; 4];
+    /// flash.read(0, &mut magic).unwrap();
+    /// assert_eq!(&magic, b"boot");
+    /// assert_eq!(flash.page_count(), 2);
+    /// ```
+    ///
+    /// Panics if the length of `data` is not a multiple of `ERASE_SIZE`.
+    pub fn from_vec(data: Vec<u8>) -> Self {
+        Self::with_contents(data.len(), data, false)
+    }
+    /// Create an erased flash, leaving contents and stuck-bit masks
+    /// unallocated when `metadata_only` is set.
+    fn allocate(size: usize, metadata_only: bool) -> Self {
+        let data = if metadata_only {
+            Vec::new()
+        } else {
+            vec![0xFF; size]
+        };
+        Self::with_contents(size, data, metadata_only)
+    }
+    /// Create a flash of `size` bytes backed by `data`, which is empty when
+    /// `metadata_only` is set.
+    fn with_contents(size: usize, data: Vec<u8>, metadata_only: bool) -> Self {
         assert_eq!(0, size % Self::ERASE_SIZE);
         let page_count = size / Self::ERASE_SIZE;
+        let stored = data.len();
         Self {
-            data: vec![0xFF; size],
-            stuck_at_1_bits: vec![0x00; size],
-            stuck_at_0_bits: vec![0x00; size],
+            size,
+            metadata_only,
+            data,
+            stuck_at_1_bits: vec![0x00; stored],
+            stuck_at_0_bits: vec![0x00; stored],
             page_cycles: vec![0; page_count],
+            subsector_size: 0,
+            subsector_cycles: Vec::new(),
+            page_endurance: vec![None; page_count],
+            page_last_erased_op: vec![None; page_count],
             read: 0,
             written: 0,
-            erased: 0,
+            written_extent: None,
+            writes_setting_bits: 0,
+            corrupt_reads: 0,
+            padding_bytes: 0,
+            program_failures: 0,
+            calibration: Calibration::default(),
+            program_units_written: 0,
+            erased_pages: 0,
+            erased_subsectors: 0,
             read_accesses: 0,
             write_accesses: 0,
             erase_accesses: 0,
             total_operations: 0,
+            record_operation_history: false,
+            operation_history: Vec::new(),
+            operation_transactions: Vec::new(),
             log_level: TransactionLogLevel::None,
             transactions: Vec::new(),
-            rng: rand::rngs::SmallRng::seed_from_u64(0),
+            transactions_dropped: 0,
+            transactions_undone: Vec::new(),
+            rng: FailureRng::new(0),
+            rng_seed: 0,
             minimum_safe_erase_cycles: u32::MAX,
-            bit_failure_every_x_erases: u32::MAX,
+            failure_model: Box::new(SimpleWearModel::new(u32::MAX)),
+            ram_semantics: false,
+            strict_writes: false,
+            skip_erased_pages: false,
+            erase_failure_rate: 0.0,
+            uninitialized_poison: None,
+            write_verify: false,
+            #[cfg(feature = "retention")]
+            retention: None,
+            unmapped_regions: Vec::new(),
+            initialized: Vec::new(),
+            programmed: Vec::new(),
+            sector_crcs: Vec::new(),
+            program_unit: WS,
+            access_granularity: AccessGranularity::PerCall,
+            read_cache: None,
+            read_cache_hits: 0,
+            read_cache_misses: 0,
             current_operation: None,
-            last_operation_stats: Default::default(),
+            operation_start_stats: Default::default(),
+            operation_stats: Vec::new(),
+            #[cfg(feature = "std")]
+            transaction_sink: None,
+            #[cfg(feature = "std")]
+            transaction_sink_errors: 0,
+            wear_threshold_callback: None,
+            failure_callback: None,
+            corrupt_read_callback: None,
+            snapshot_sampler: None,
+            low_space_monitor: None,
+            #[cfg(feature = "std")]
+            realtime_playback: None,
         }
     }
     /// Create a flash and configure failure model and RNG seed.
     ///
     /// Use this to simulate wear-out behavior without a separate builder.
+    /// Without a seed, a single `u64` is drawn from OS entropy and used as
+    /// the seed. Read it back via [`rng_seed`](Self::rng_seed) (or the
+    /// snapshot's `rng_seed`) to reproduce the run later; with the `log`
+    /// feature it is also logged at info level. When the `std` feature is
+    /// disabled the seed `0` is used instead.
     pub fn new_with_failures(
         size: usize,
         minimum_erase_cycles: u32,
         bit_failure_every_x_erases: u32,
         rng_seed: Option<u64>,
     ) -> Self {
+        Self::new(size).with_failures(minimum_erase_cycles, bit_failure_every_x_erases, rng_seed)
+    }
+    /// Start a [`SimulatedNorFlashBuilder`] for a flash of `size` bytes.
+    ///
+    /// The geometry of the built flash is inferred from the target type, so
+    /// together with the type aliases no turbofish is needed:
+    ///
+    /// ```
+    /// use embedded_storage_sim::SimulatedNorFlashR1W4E4k;
+    ///
+    /// let flash: SimulatedNorFlashR1W4E4k =
+    ///     <SimulatedNorFlashR1W4E4k>::builder(64 * 1024).with_rng_seed(1).build();
+    /// assert_eq!(flash.size(), 64 * 1024);
+    ///
+    /// let flash: SimulatedNorFlashR1W4E4k = SimulatedNorFlashR1W4E4k::new(64 * 1024);
+    /// assert_eq!(flash.page_count(), 16);
+    /// ```
+    pub fn builder(size: usize) -> SimulatedNorFlashBuilder {
+        SimulatedNorFlashBuilder::new(size)
+    }
+    /// Configure the failure model and RNG seed, see [`new_with_failures`](Self::new_with_failures).
+    fn with_failures(
+        self,
+        minimum_erase_cycles: u32,
+        bit_failure_every_x_erases: u32,
+        rng_seed: Option<u64>,
+    ) -> Self {
+        let rng_seed = match rng_seed {
+            Some(seed) => seed,
+            #[cfg(feature = "std")]
+            None => os_rng_seed(),
+            #[cfg(not(feature = "std"))]
+            None => 0,
+        };
         Self {
             minimum_safe_erase_cycles: minimum_erase_cycles,
-            bit_failure_every_x_erases,
-            rng: match rng_seed {
-                Some(seed) => rand::rngs::SmallRng::seed_from_u64(seed),
-                None => rand::rngs::SmallRng::from_os_rng(),
-            },
-            ..Self::new(size)
+            failure_model: Box::new(SimpleWearModel::new(bit_failure_every_x_erases)),
+            rng: FailureRng::new(rng_seed),
+            rng_seed,
+            ..self
         }
     }
+    /// Seed the failure injection RNG was initialized with.
+    ///
+    /// Pass it to [`SimulatedNorFlashBuilder::with_rng_seed`] to reproduce
+    /// the exact same failure sequence.
+    pub fn rng_seed(&self) -> u64 {
+        self.rng_seed
+    }
+    /// Number of values drawn from the failure injection RNG so far.
+    ///
+    /// Together with [`rng_seed`](Self::rng_seed) this captures the exact
+    /// RNG state, so a saved run can continue with identical defect
+    /// placement via [`restore_rng`](Self::restore_rng):
+    ///
+    /// ```
+    /// use embedded_storage::nor_flash::NorFlash;
+    /// use embedded_storage_sim::{SimulatedNorFlash, SimulatedNorFlashBuilder};
+    /// use std::sync::mpsc;
+    ///
+    /// let builder = |seed| {
+    ///     SimulatedNorFlashBuilder::new(4096)
+    ///         .with_rng_seed(seed)
+    ///         .with_minimum_erase_cycles(0)
+    ///         .with_failure_rate(1)
+    /// };
+    /// let mut flash: SimulatedNorFlash = builder(7).build();
+    /// for _ in 0..10 {
+    ///     flash.erase(0, 4096).unwrap();
+    /// }
+    /// let (seed, draws) = (flash.rng_seed(), flash.rng_draws());
+    /// let cycles = flash.page_erase_cycles().to_vec();
+    ///
+    /// let mut restored: SimulatedNorFlash =
+    ///     builder(0).with_initial_page_cycles(cycles).build();
+    /// restored.restore_rng(seed, draws);
+    ///
+    /// let (tx, original_events) = mpsc::channel();
+    /// flash.set_failure_callback(move |event| tx.send(event).unwrap());
+    /// let (tx, restored_events) = mpsc::channel();
+    /// restored.set_failure_callback(move |event| tx.send(event).unwrap());
+    /// for _ in 0..10 {
+    ///     flash.erase(0, 4096).unwrap();
+    ///     restored.erase(0, 4096).unwrap();
+    /// }
+    /// drop((flash, restored));
+    /// let original: Vec<_> = original_events.iter().collect();
+    /// assert_eq!(original.len(), 10);
+    /// assert_eq!(original, restored_events.iter().collect::<Vec<_>>());
+    /// ```
+    pub fn rng_draws(&self) -> u64 {
+        self.rng.draws()
+    }
+    /// Reseed the failure injection RNG with `seed` and fast-forward it by
+    /// `draws` values, as captured by [`rng_draws`](Self::rng_draws).
+    ///
+    /// Only the RNG is restored; stored contents, stuck bits and erase
+    /// cycles are left unchanged.
+    pub fn restore_rng(&mut self, seed: u64, draws: u64) {
+        self.rng = FailureRng::restore(seed, draws);
+        self.rng_seed = seed;
+    }
     /// Set the transaction logging level for subsequent operations.
     pub fn set_logging(&mut self, level: TransactionLogLevel) {
         self.log_level = level;
     }
-    /// Attach an operation tag to the next transaction(s).
+    /// Overwrite the per-page erase cycle counters, e.g. to model an aged device.
     ///
-    /// Useful for correlating storage activity with high-level actions in
-    /// higher layers. The tag is stored in emitted [`Transaction`]s.
-    pub fn start_operation(&mut self, operation: O) {
-        self.current_operation = Some(operation);
-        self.total_operations += 1;
-        self.last_operation_stats = self.stats();
+    /// Panics if `page_cycles` doesn't have exactly [`page_count`] entries.
+    ///
+    /// [`page_count`]: Self::page_count
+    pub fn set_page_cycles(&mut self, page_cycles: &[u32]) {
+        assert_eq!(
+            self.page_cycles.len(),
+            page_cycles.len(),
+            "page cycle count must match the page count"
+        );
+        self.page_cycles.copy_from_slice(page_cycles);
+        for (page_index, &cycles) in page_cycles.iter().enumerate() {
+            let subsectors = self.subsectors_of_page(page_index);
+            self.subsector_cycles[subsectors].fill(cycles);
+        }
+    }
+    /// Override the safe erase-cycle limit of a single page.
+    ///
+    /// Pages without an override use the global limit from
+    /// [`SimulatedNorFlashBuilder::with_minimum_erase_cycles`]. Use this to
+    /// model a deliberately weak (or strong) sector.
+    pub fn set_page_endurance(&mut self, page_index: usize, cycles: u32) {
+        self.page_endurance[page_index] = Some(cycles);
+    }
+    /// Safe erase-cycle limit in effect for `page_index`.
+    pub fn page_endurance(&self, page_index: usize) -> u32 {
+        self.page_endurance[page_index].unwrap_or(self.minimum_safe_erase_cycles)
+    }
+    /// Register a callback invoked when a page first exceeds the safe erase-cycle limit.
+    ///
+    /// The callback receives the page index and its new erase cycle count. It
+    /// fires once per page, during the [`erase`] that pushes the page past its
+    /// [`page_endurance`](Self::page_endurance). Resetting the wear counters
+    /// re-arms it.
+    ///
+    /// [`erase`]: embedded_storage::nor_flash::NorFlash::erase
+    pub fn set_wear_threshold_callback(
+        &mut self,
+        callback: impl FnMut(usize, u32) + Send + 'static,
+    ) {
+        self.wear_threshold_callback = Some(Box::new(callback));
     }
+    /// Register a callback invoked for every stuck bit injected during an erase.
+    ///
+    /// This gives a clean event stream of defect accumulation without
+    /// diffing the stuck-bit masks after every erase.
+    pub fn set_failure_callback(&mut self, callback: impl FnMut(FailureEvent) + Send + 'static) {
+        self.failure_callback = Some(Box::new(callback));
+    }
+    /// Register a callback invoked with the offset of every byte a read
+    /// returns differently from the stored contents because of stuck bits.
+    ///
+    /// This signals that a defect was exercised without diffing against
+    /// expected data. Each such read is also counted in
+    /// [`corrupt_reads`](Self::corrupt_reads).
+    pub fn set_corrupt_read_callback(&mut self, callback: impl FnMut(u32) + Send + 'static) {
+        self.corrupt_read_callback = Some(Box::new(callback));
+    }
+    /// Replace the failure policy applied to subsequently erased pages.
+    ///
+    /// Stuck bits injected so far are kept.
+    pub fn set_failure_model(&mut self, model: impl FailureModel + 'static) {
+        self.failure_model = Box::new(model);
+    }
+    /// Stats accumulated since the last [`start_operation`].
+    ///
+    /// All counters of [`FlashStats`] are snapshotted when an operation is
+    /// started, and this returns the difference to the current values. It
+    /// therefore covers every read, write and erase issued since then, no
+    /// matter how many, including untagged accesses after
+    /// [`end_operation`]. Since the snapshot is taken after counting the new
+    /// operation, `total_operations` is always `0`. After
+    /// [`reset_stats`](Self::reset_stats) the delta is measured from the
+    /// reset instead.
+    ///
+    /// ```
+    /// use embedded_storage::nor_flash::{NorFlash, ReadNorFlash};
+    /// use embedded_storage_sim::SimulatedNorFlash;
+    ///
+    /// let mut flash: SimulatedNorFlash<&str> = SimulatedNorFlash::new(2 * 4096);
+    /// flash.write(0, &[0; 64]).unwrap();
+    ///
+    /// flash.start_operation("store");
+    /// let mut header = [0; 8];
+    /// flash.read(0, &mut header).unwrap();
+    /// for offset in [64, 80, 96] {
+    ///     flash.write(offset, &[0; 16]).unwrap();
+    /// }
+    /// flash.erase(4096, 2 * 4096).unwrap();
+    ///
+    /// let stats = flash.last_operation_stats();
+    /// assert_eq!((stats.bytes_read, stats.read_accesses), (8, 1));
+    /// assert_eq!((stats.bytes_written, stats.write_accesses), (48, 3));
+    /// assert_eq!((stats.pages_erased, stats.erase_accesses), (1, 1));
+    /// assert_eq!(stats.total_operations, 0);
+    /// ```
+    ///
+    /// [`start_operation`]: Self::start_operation
+    /// [`end_operation`]: Self::end_operation
     pub fn last_operation_stats(&self) -> FlashStats {
-        &self.stats() - &self.last_operation_stats
+        &self.stats() - &self.operation_start_stats
     }
     /// Erase all data and clear statistics and injected failures.
+    ///
+    /// When the flash is owned by a storage layer with its own cache, such
+    /// as `sequential_storage::Storage`, resetting the flash underneath it
+    /// leaves that cache describing contents that no longer exist. For
+    /// repeated benchmark phases, reclaim the flash and cache with the
+    /// storage layer's `destroy`, reset the flash, and build a new storage
+    /// around it with a fresh (or explicitly invalidated) cache. To keep
+    /// the contents but start counting from zero, use
+    /// [`reset_stats`](Self::reset_stats) instead, which is always safe.
     pub fn reset(&mut self) {
-        self.data.fill(0xFF);
+        let initial_value = self.initial_value();
+        self.data.fill(initial_value);
+        self.initialized.fill(false);
+        self.programmed.fill(false);
+        self.update_sector_crcs(0..self.size());
+        self.invalidate_read_cache(0..self.size());
+        self.check_low_space(false);
         self.reset_stats();
         self.reset_failures();
     }
     /// Clear counters, transactions, and per-page erase cycle tracking.
+    ///
+    /// Use [`reset_counters`](Self::reset_counters) to keep the wear.
     pub fn reset_stats(&mut self) {
+        self.reset_counters();
+        self.page_cycles.fill(0);
+        self.subsector_cycles.fill(0);
+    }
+    /// Clear the access counters and transactions but keep the device state.
+    ///
+    /// Unlike [`reset_stats`](Self::reset_stats) this leaves erase cycle
+    /// counts, stuck bits and contents intact, so a benchmark phase starts
+    /// counting from zero on a device with its accumulated wear:
+    ///
+    /// ```
+    /// use embedded_storage::nor_flash::NorFlash;
+    /// use embedded_storage_sim::SimulatedNorFlash;
+    ///
+    /// let mut flash: SimulatedNorFlash = SimulatedNorFlash::new(4096);
+    /// flash.erase(0, 4096).unwrap();
+    /// flash.reset_counters();
+    /// assert_eq!(flash.pages_erased(), 0);
+    /// assert_eq!(flash.page_erase_cycles(), [1]);
+    /// flash.reset_stats();
+    /// assert_eq!(flash.page_erase_cycles(), [0]);
+    /// ```
+    pub fn reset_counters(&mut self) {
         self.read = 0;
         self.written = 0;
-        self.erased = 0;
+        self.written_extent = None;
+        self.writes_setting_bits = 0;
+        self.corrupt_reads = 0;
+        self.read_cache_hits = 0;
+        self.read_cache_misses = 0;
+        self.padding_bytes = 0;
+        self.program_failures = 0;
+        self.program_units_written = 0;
+        self.erased_pages = 0;
+        self.erased_subsectors = 0;
         self.read_accesses = 0;
         self.write_accesses = 0;
         self.erase_accesses = 0;
         self.total_operations = 0;
+        self.page_last_erased_op.fill(None);
+        self.operation_history.clear();
+        self.operation_transactions.clear();
+        self.transactions_dropped = self
+            .transactions_dropped
+            .saturating_add(self.transactions.len())
+            .saturating_add(self.transactions_skipped());
         self.transactions.clear();
-        self.page_cycles.fill(0);
+        self.transactions_undone.clear();
         self.current_operation = None;
+        self.operation_start_stats = Default::default();
+        self.operation_stats.clear();
     }
     /// Remove all injected stuck-bit failures and reset wear counters.
     pub fn reset_failures(&mut self) {
         self.stuck_at_0_bits.fill(0);
         self.stuck_at_1_bits.fill(0);
         self.page_cycles.fill(0);
+        self.subsector_cycles.fill(0);
+    }
+    /// Restore a previously captured data image and clear statistics.
+    ///
+    /// This is much cheaper than replaying the workload that produced the
+    /// image, e.g. between benchmark phases. When `reset_wear` is `true`,
+    /// erase cycle counters and injected failures are cleared as well;
+    /// otherwise the accumulated wear is kept.
+    ///
+    /// Panics if `image` is not exactly [`size`](Self::size) bytes long.
+    pub fn reset_to(&mut self, image: &[u8], reset_wear: bool) {
+        assert_eq!(image.len(), self.size());
+        if !self.metadata_only {
+            self.data.copy_from_slice(image);
+        }
+        self.update_sector_crcs(0..self.size());
+        self.invalidate_read_cache(0..self.size());
+        self.initialized.fill(true);
+        // bytes that aren't blank count as programmed
+        for (programmed, &byte) in self.programmed.iter_mut().zip(image) {
+            *programmed = byte != 0xff;
+        }
+        self.check_low_space(false);
+        self.reset_counters();
+        if reset_wear {
+            self.reset_failures();
+        }
+    }
+    /// Contents of a freshly reset device: `0xFF` for NOR, `0x00` with RAM semantics.
+    fn initial_value(&self) -> u8 {
+        if self.ram_semantics { 0x00 } else { 0xFF }
     }
     /// Total flash capacity in bytes.
     pub fn size(&self) -> usize {
-        self.data.len()
+        self.size
     }
     /// Number of erase units (pages) in the flash.
     pub fn page_count(&self) -> usize {
         self.page_cycles.len()
     }
+    /// Borrow the raw stored contents without copying.
+    ///
+    /// These are the contents as programmed, before stuck bits are applied:
+    /// unlike [`read`] this applies no stuck-bit masks, doesn't count
+    /// towards the statistics and isn't logged. Use [`effective_byte`] for
+    /// the value a read returns. Empty without stored contents.
+    ///
+    /// [`read`]: embedded_storage::nor_flash::ReadNorFlash::read
+    /// [`effective_byte`]: Self::effective_byte
+    pub fn data(&self) -> &[u8] {
+        &self.data
+    }
+    /// The byte at `offset` as a read would return it, with stuck bits applied.
+    ///
+    /// Like [`data`](Self::data) this doesn't count towards the statistics
+    /// and isn't logged.
+    ///
+    /// Panics if `offset` is out of bounds.
+    pub fn effective_byte(&self, offset: u32) -> u8 {
+        let i = offset as usize;
+        assert!(i < self.size());
+        if self.metadata_only {
+            self.initial_value()
+        } else {
+            (self.data[i] | self.stuck_at_1_bits[i]) & !self.stuck_at_0_bits[i]
+        }
+    }
+    /// Whether every byte in `range` reads back as erased (`0xFF`).
+    ///
+    /// Stuck bits are applied like in [`effective_byte`](Self::effective_byte),
+    /// and nothing is counted or logged. An empty range is erased.
+    ///
+    /// Panics if `range` is out of bounds.
+    pub fn is_region_erased(&self, range: Range<u32>) -> bool {
+        assert!(range.end as usize <= self.size());
+        range
+            .into_iter()
+            .all(|offset| self.effective_byte(offset) == 0xff)
+    }
+    /// Start of the first window of `len` erased bytes at or after `from`.
+    ///
+    /// Useful to try out allocation strategies that look for blank space:
+    ///
+    /// ```
+    /// use embedded_storage::nor_flash::NorFlash;
+    /// use embedded_storage_sim::SimulatedNorFlash;
+    ///
+    /// let mut flash: SimulatedNorFlash = SimulatedNorFlash::new(4096);
+    /// flash.write(0, &[0; 16]).unwrap();
+    /// flash.write(20, &[0; 4]).unwrap();
+    /// assert!(!flash.is_region_erased(0..32));
+    /// assert!(flash.is_region_erased(16..20));
+    /// assert_eq!(flash.next_erased_run(0, 4), Some(16));
+    /// assert_eq!(flash.next_erased_run(0, 5), Some(24));
+    /// assert_eq!(flash.next_erased_run(4000, 100), None);
+    /// ```
+    pub fn next_erased_run(&self, from: u32, len: u32) -> Option<u32> {
+        let mut start = from;
+        for offset in from..self.size() as u32 {
+            if offset - start >= len {
+                break;
+            }
+            if self.effective_byte(offset) != 0xff {
+                start = offset + 1;
+            }
+        }
+        (start as usize + len as usize <= self.size()).then_some(start)
+    }
+    /// Erased bytes as they read back, either in total or the longest run.
+    ///
+    /// Unlike [`free_bytes_estimate`](Self::free_bytes_estimate) this
+    /// doesn't assume an append-only layout.
+    pub fn free_bytes(&self, measure: FreeSpace) -> usize {
+        let erased = (0..self.size() as u32).map(|offset| self.effective_byte(offset) == 0xff);
+        match measure {
+            FreeSpace::Total => erased.filter(|&erased| erased).count(),
+            FreeSpace::Contiguous => {
+                let (mut run, mut longest) = (0, 0);
+                for erased in erased {
+                    run = if erased { run + 1 } else { 0 };
+                    longest = longest.max(run);
+                }
+                longest
+            }
+        }
+    }
+    /// Register a callback invoked when a write makes the
+    /// [`free_bytes`](Self::free_bytes) drop below `threshold`.
+    ///
+    /// It fires once per crossing: after an erase or reset brings the free
+    /// space back to `threshold` or more, the next write that drops below
+    /// it fires again. Replaces any previously registered callback. Free
+    /// space is recounted after every write and erase, which costs time
+    /// proportional to the flash size.
+    ///
+    /// ```
+    /// use std::sync::{
+    ///     Arc,
+    ///     atomic::{AtomicUsize, Ordering},
+    /// };
+    ///
+    /// use embedded_storage::nor_flash::NorFlash;
+    /// use embedded_storage_sim::{FreeSpace, SimulatedNorFlash};
+    ///
+    /// let mut flash: SimulatedNorFlash = SimulatedNorFlash::new(2 * 4096);
+    /// let fired = Arc::new(AtomicUsize::new(0));
+    /// let counter = Arc::clone(&fired);
+    /// flash.set_low_space_callback(4096, FreeSpace::Contiguous, move || {
+    ///     counter.fetch_add(1, Ordering::Relaxed);
+    /// });
+    /// flash.write(2048, &[0; 16]).unwrap();
+    /// flash.write(6144, &[0; 16]).unwrap();
+    /// assert_eq!(fired.load(Ordering::Relaxed), 1);
+    /// flash.write(0, &[0; 16]).unwrap();
+    /// assert_eq!(fired.load(Ordering::Relaxed), 1);
+    ///
+    /// flash.erase(0, 2 * 4096).unwrap();
+    /// flash.write(4088, &[0; 16]).unwrap();
+    /// assert_eq!(fired.load(Ordering::Relaxed), 2);
+    /// ```
+    pub fn set_low_space_callback(
+        &mut self,
+        threshold: usize,
+        measure: FreeSpace,
+        callback: impl FnMut() + Send + 'static,
+    ) {
+        self.low_space_monitor = Some(LowSpaceMonitor {
+            threshold,
+            measure,
+            below: self.free_bytes(measure) < threshold,
+            callback: Box::new(callback),
+        });
+    }
+    /// Recount the free space for the low-space callback and invoke it if a
+    /// write dropped the free space below its threshold.
+    fn check_low_space(&mut self, after_write: bool) {
+        let Some(monitor) = &self.low_space_monitor else {
+            return;
+        };
+        let below = self.free_bytes(monitor.measure) < monitor.threshold;
+        if let Some(monitor) = self.low_space_monitor.as_mut() {
+            if below && !monitor.below && after_write {
+                (monitor.callback)();
+            }
+            monitor.below = below;
+        }
+    }
+    /// Erased bytes at the end of each page, summed over all pages.
+    ///
+    /// Append-only layouts like `sequential_storage`'s queue and map fill
+    /// each page front to back, so this is roughly the space they can still
+    /// use without erasing. Erased gaps before the last programmed byte of a
+    /// page are not counted.
+    pub fn free_bytes_estimate(&self) -> usize {
+        (0..self.page_count())
+            .map(|page| self.page_free_bytes(page))
+            .sum()
+    }
+    /// Estimated number of items of `item_len` bytes that still fit.
+    ///
+    /// Each item takes `item_len + overhead` bytes (e.g. a header and CRC),
+    /// rounded up to `WRITE_SIZE`, and items don't span pages. This gives
+    /// capacity intuition only; the exact accounting is up to the storage
+    /// layer.
+    ///
+    /// ```
+    /// use embedded_storage::nor_flash::NorFlash;
+    /// use embedded_storage_sim::SimulatedNorFlash;
+    ///
+    /// let mut flash: SimulatedNorFlash = SimulatedNorFlash::new(2 * 4096);
+    /// flash.write(0, &[0; 96]).unwrap();
+    /// assert_eq!(flash.free_bytes_estimate(), 2 * 4096 - 96);
+    /// assert_eq!(flash.items_remaining(24, 8), 4000 / 32 + 4096 / 32);
+    /// ```
+    pub fn items_remaining(&self, item_len: usize, overhead: usize) -> usize {
+        let slot = (item_len + overhead)
+            .next_multiple_of(Self::WRITE_SIZE)
+            .max(1);
+        (0..self.page_count())
+            .map(|page| self.page_free_bytes(page) / slot)
+            .sum()
+    }
+    /// Erased bytes after the last programmed byte of page `page_index`.
+    fn page_free_bytes(&self, page_index: usize) -> usize {
+        let page = page_index * Self::ERASE_SIZE;
+        (page..page + Self::ERASE_SIZE)
+            .rev()
+            .take_while(|&offset| self.effective_byte(offset as u32) == 0xff)
+            .count()
+    }
+    /// Stable 64-bit FNV-1a hash of the contents as they read back.
+    ///
+    /// Stuck bits are applied, so two flashes with the same hash return the
+    /// same data on every read. This is much cheaper to compare and log than
+    /// the full contents, e.g. to check that replaying a log reproduces the
+    /// original run:
+    ///
+    /// ```
+    /// use embedded_storage::nor_flash::NorFlash;
+    /// use embedded_storage_sim::{
+    ///     SimulatedNorFlash, SimulatedNorFlashBuilder, Transaction, TransactionLogLevel,
+    /// };
+    ///
+    /// let mut original: SimulatedNorFlash = SimulatedNorFlashBuilder::new(2 * 4096)
+    ///     .with_logging(TransactionLogLevel::WriteDataOnly)
+    ///     .build();
+    /// original.write(0, b"hello").unwrap();
+    /// original.erase(0, 4096).unwrap();
+    /// original.write(4096, b"world").unwrap();
+    ///
+    /// let mut replay: SimulatedNorFlash = SimulatedNorFlash::new(2 * 4096);
+    /// for transaction in original.transactions() {
+    ///     match transaction {
+    ///         Transaction::Write { offset, data: Some(data), .. } => {
+    ///             replay.write(*offset, data).unwrap()
+    ///         }
+    ///         Transaction::Erase { from, to, .. } => replay.erase(*from, *to).unwrap(),
+    ///         _ => {}
+    ///     }
+    /// }
+    /// assert_eq!(replay.content_hash(), original.content_hash());
+    /// ```
+    ///
+    /// Without stored contents this is the hash of a fully erased device.
+    pub fn content_hash(&self) -> u64 {
+        let mut hash = 0xcbf2_9ce4_8422_2325u64;
+        for offset in 0..self.size() as u32 {
+            hash = (hash ^ self.effective_byte(offset) as u64).wrapping_mul(0x0000_0100_0000_01b3);
+        }
+        hash
+    }
+    /// CRC-32 of each page's stored contents, indexed like
+    /// [`page_erase_cycles`](Self::page_erase_cycles).
+    ///
+    /// Only maintained with
+    /// [`with_sector_crcs`](SimulatedNorFlashBuilder::with_sector_crcs),
+    /// empty otherwise. Like [`data`](Self::data) this covers the contents
+    /// as programmed, before stuck bits are applied. Comparing the CRCs of
+    /// two checkpoints finds the changed pages without comparing every byte:
+    ///
+    /// ```
+    /// use embedded_storage::nor_flash::NorFlash;
+    /// use embedded_storage_sim::{SimulatedNorFlash, SimulatedNorFlashBuilder};
+    ///
+    /// let mut flash: SimulatedNorFlash = SimulatedNorFlashBuilder::new(4 * 4096)
+    ///     .with_sector_crcs(true)
+    ///     .build();
+    /// let before = flash.sector_crcs().to_vec();
+    /// flash.write(2 * 4096 + 17, &[0x5a]).unwrap();
+    /// let changed: Vec<_> = (0..before.len())
+    ///     .filter(|&page| flash.sector_crcs()[page] != before[page])
+    ///     .collect();
+    /// assert_eq!(changed, [2]);
+    ///
+    /// flash.erase(2 * 4096, 3 * 4096).unwrap();
+    /// assert_eq!(flash.sector_crcs(), before);
+    /// ```
+    pub fn sector_crcs(&self) -> &[u32] {
+        &self.sector_crcs
+    }
+    /// Recompute the [`sector_crcs`](Self::sector_crcs) of all pages
+    /// overlapping the byte `range`, if they are maintained.
+    fn update_sector_crcs(&mut self, range: Range<usize>) {
+        if self.sector_crcs.is_empty() {
+            return;
+        }
+        let pages = range.start / Self::ERASE_SIZE..range.end.div_ceil(Self::ERASE_SIZE);
+        for page in pages {
+            let start = page * Self::ERASE_SIZE;
+            self.sector_crcs[page] = crc32(&self.data[start..start + Self::ERASE_SIZE]);
+        }
+    }
+    /// Whether both flashes read back the same contents.
+    ///
+    /// Compares with stuck bits applied, like [`content_hash`](Self::content_hash),
+    /// but exactly. Flashes of different sizes are never equal.
+    pub fn bytes_equal(&self, other: &Self) -> bool {
+        self.size() == other.size() && self.first_difference(other).is_none()
+    }
+    /// Offset of the first byte that reads back differently in `other`.
+    ///
+    /// Only the common prefix is compared; if it matches but the sizes
+    /// differ, this is the end of the smaller flash. Useful for differential
+    /// testing, where two configurations must leave the same contents:
+    ///
+    /// ```
+    /// use embedded_storage::nor_flash::NorFlash;
+    /// use embedded_storage_sim::SimulatedNorFlash;
+    ///
+    /// let mut a: SimulatedNorFlash = SimulatedNorFlash::new(4096);
+    /// let mut b: SimulatedNorFlash = SimulatedNorFlash::new(4096);
+    /// a.write(0, b"same").unwrap();
+    /// b.write(0, b"same").unwrap();
+    /// assert!(a.bytes_equal(&b));
+    /// b.write(100, &[0x7f]).unwrap();
+    /// assert_eq!(a.first_difference(&b), Some(100));
+    /// assert!(a.page_cycles_equal(&b));
+    /// ```
+    pub fn first_difference(&self, other: &Self) -> Option<u32> {
+        let len = self.size().min(other.size()) as u32;
+        (0..len)
+            .find(|&offset| self.effective_byte(offset) != other.effective_byte(offset))
+            .or((self.size() != other.size()).then_some(len))
+    }
+    /// Whether both flashes have the same per-page erase cycle counts.
+    ///
+    /// Combine with [`bytes_equal`](Self::bytes_equal) to check that a
+    /// change doesn't alter wear either.
+    pub fn page_cycles_equal(&self, other: &Self) -> bool {
+        self.page_cycles == other.page_cycles
+    }
     pub fn stats(&self) -> FlashStats {
         FlashStats {
             bytes_read: self.read,
             bytes_written: self.written,
-            pages_erased: self.erased / Self::ERASE_SIZE,
+            program_units_written: self.program_units_written,
+            pages_erased: self.erased_pages,
+            subsectors_erased: self.erased_subsectors,
             read_accesses: self.read_accesses,
             write_accesses: self.write_accesses,
             erase_accesses: self.erase_accesses,
             total_operations: self.total_operations,
         }
     }
+    /// Continue counting from `stats`, e.g. saved with a snapshot to
+    /// resume a long run together with [`restore_rng`](Self::restore_rng).
+    ///
+    /// [`last_operation_stats`](Self::last_operation_stats) is measured
+    /// from the restored values. Counters saturate at `usize::MAX`:
+    ///
+    /// ```
+    /// use embedded_storage::nor_flash::{NorFlash, ReadNorFlash};
+    /// use embedded_storage_sim::{FlashStats, SimulatedNorFlash};
+    ///
+    /// let mut flash: SimulatedNorFlash<&str> = SimulatedNorFlash::new(4096);
+    /// let near_max = usize::MAX - 1;
+    /// flash.restore_stats(&FlashStats {
+    ///     bytes_read: near_max,
+    ///     bytes_written: near_max,
+    ///     program_units_written: near_max,
+    ///     pages_erased: near_max,
+    ///     subsectors_erased: 0,
+    ///     read_accesses: near_max,
+    ///     write_accesses: near_max,
+    ///     erase_accesses: near_max,
+    ///     total_operations: near_max,
+    /// });
+    /// for _ in 0..2 {
+    ///     flash.start_operation("cycle");
+    ///     flash.erase(0, 4096).unwrap();
+    ///     flash.write(0, &[0; 16]).unwrap();
+    ///     let mut buf = [0; 16];
+    ///     flash.read(0, &mut buf).unwrap();
+    /// }
+    /// let stats = flash.stats();
+    /// assert_eq!((stats.bytes_read, stats.bytes_written), (usize::MAX, usize::MAX));
+    /// assert_eq!((stats.pages_erased, stats.total_operations), (usize::MAX, usize::MAX));
+    /// assert_eq!(stats.read_accesses, usize::MAX);
+    /// assert_eq!(flash.last_operation_stats().bytes_read, 0);
+    /// ```
+    pub fn restore_stats(&mut self, stats: &FlashStats) {
+        self.read = stats.bytes_read;
+        self.written = stats.bytes_written;
+        self.program_units_written = stats.program_units_written;
+        self.erased_pages = stats.pages_erased;
+        self.erased_subsectors = stats.subsectors_erased;
+        self.read_accesses = stats.read_accesses;
+        self.write_accesses = stats.write_accesses;
+        self.erase_accesses = stats.erase_accesses;
+        self.total_operations = stats.total_operations;
+        self.operation_start_stats = stats.clone();
+    }
     /// Total amount of bytes read since last stats reset.
     pub fn bytes_read(&self) -> usize {
         self.read
@@ -342,17 +2524,247 @@ impl<O: Clone, const RS: usize, const WS: usize, const ES: usize> SimulatedNorFl
     pub fn bytes_written(&self) -> usize {
         self.written
     }
+    /// Lowest to highest byte written since last stats reset, or `None` if
+    /// nothing was written.
+    ///
+    /// Erases don't shrink the extent. Use it to see whether writes are
+    /// spread across the whole device or packed into a small region.
+    pub fn written_extent(&self) -> Option<Range<u32>> {
+        self.written_extent.clone()
+    }
+    /// Number of writes since last stats reset that tried to set a bit
+    /// which an earlier write had cleared.
+    ///
+    /// NOR flash silently stores the AND of old and new value in that case,
+    /// which usually means the caller forgot to erase in between. Such
+    /// writes are counted even when they are rejected with
+    /// [`FlashError::WriteToNonErased`] in strict mode (see
+    /// [`with_strict_writes`](SimulatedNorFlashBuilder::with_strict_writes)).
+    /// Never counted with RAM semantics or without stored contents.
+    ///
+    /// ```
+    /// use embedded_storage::nor_flash::{MultiwriteNorFlash, ReadNorFlash};
+    /// use embedded_storage_sim::{FlashError, SimulatedNorFlash, SimulatedNorFlashBuilder};
+    ///
+    /// fn rewrite<F: MultiwriteNorFlash>(flash: &mut F, value: u8) -> Result<u8, F::Error> {
+    ///     flash.write(0, &[value])?;
+    ///     let mut byte = [0];
+    ///     flash.read(0, &mut byte)?;
+    ///     Ok(byte[0])
+    /// }
+    ///
+    /// let mut flash: SimulatedNorFlash = SimulatedNorFlash::new(4096);
+    /// assert_eq!(rewrite(&mut flash, 0b1100_1100), Ok(0b1100_1100));
+    /// // only clears bits: fine for multiwrite
+    /// assert_eq!(rewrite(&mut flash, 0b1000_1100), Ok(0b1000_1100));
+    /// assert_eq!(flash.writes_setting_bits(), 0);
+    /// // tries to set bit 5 again: bits can only clear, so the AND is stored
+    /// assert_eq!(rewrite(&mut flash, 0b1010_1000), Ok(0b1000_1000));
+    /// assert_eq!(flash.writes_setting_bits(), 1);
+    ///
+    /// let mut strict: SimulatedNorFlash = SimulatedNorFlashBuilder::new(4096)
+    ///     .with_strict_writes(true)
+    ///     .build();
+    /// assert_eq!(rewrite(&mut strict, 0b1100_1100), Ok(0b1100_1100));
+    /// assert_eq!(
+    ///     rewrite(&mut strict, 0b1010_1000),
+    ///     Err(FlashError::WriteToNonErased { offset: 0 })
+    /// );
+    /// assert_eq!(strict.data()[0], 0b1100_1100);
+    /// assert_eq!(strict.writes_setting_bits(), 1);
+    /// ```
+    pub fn writes_setting_bits(&self) -> usize {
+        self.writes_setting_bits
+    }
+    /// Number of reads since last stats reset that returned at least one
+    /// byte altered by a stuck bit.
+    ///
+    /// See [`set_corrupt_read_callback`](Self::set_corrupt_read_callback)
+    /// to find out which bytes were affected.
+    pub fn corrupt_reads(&self) -> usize {
+        self.corrupt_reads
+    }
+    /// Number of reads since last stats reset served entirely from the
+    /// [`read cache`](SimulatedNorFlashBuilder::with_read_cache).
+    pub fn read_cache_hits(&self) -> usize {
+        self.read_cache_hits
+    }
+    /// Number of reads since last stats reset that went to the bus although
+    /// a [`read cache`](SimulatedNorFlashBuilder::with_read_cache) is set.
+    pub fn read_cache_misses(&self) -> usize {
+        self.read_cache_misses
+    }
+    /// Number of bits since last stats reset that a write failed to clear,
+    /// see [`FailureModel::on_program`].
+    pub fn program_failures(&self) -> usize {
+        self.program_failures
+    }
+    /// Count `bytes` of the data written so far as alignment padding.
+    ///
+    /// The simulator can't tell padding from payload, so the caller marks
+    /// it, e.g. with [`padding_for`](Self::padding_for) for every item it
+    /// stores. This doesn't change any other statistic.
+    ///
+    /// ```
+    /// use embedded_storage::nor_flash::NorFlash;
+    /// use embedded_storage_sim::SimulatedNorFlashR1W4E4k;
+    ///
+    /// let mut flash: SimulatedNorFlashR1W4E4k = SimulatedNorFlashR1W4E4k::new(4096);
+    /// let item = b"hello";
+    /// let padding = SimulatedNorFlashR1W4E4k::<()>::padding_for(item.len());
+    /// let mut buf = [0xff; 8];
+    /// buf[..item.len()].copy_from_slice(item);
+    /// flash.write(0, &buf).unwrap();
+    /// flash.count_padding(padding);
+    /// assert_eq!((flash.payload_bytes(), flash.padding_bytes()), (5, 3));
+    /// ```
+    pub fn count_padding(&mut self, bytes: usize) {
+        self.padding_bytes = self.padding_bytes.saturating_add(bytes);
+    }
+    /// Bytes marked as padding with [`count_padding`](Self::count_padding)
+    /// since last stats reset.
+    pub fn padding_bytes(&self) -> usize {
+        self.padding_bytes
+    }
+    /// Bytes written since last stats reset that were not marked as padding.
+    pub fn payload_bytes(&self) -> usize {
+        self.written.saturating_sub(self.padding_bytes)
+    }
+    /// Number of program units touched by writes since last stats reset.
+    pub fn program_units_written(&self) -> usize {
+        self.program_units_written
+    }
+    /// Number of program units covered by `length` bytes at `offset`.
+    fn program_units(&self, offset: u32, length: usize) -> usize {
+        let start = offset as usize / self.program_unit;
+        (offset as usize + length).div_ceil(self.program_unit) - start
+    }
+    /// Number of accesses counted for a non-empty access of `length` bytes
+    /// at `offset`, see [`AccessGranularity`].
+    fn accesses(&self, offset: u32, length: usize) -> usize {
+        let block = match self.access_granularity {
+            AccessGranularity::PerCall => return 1,
+            AccessGranularity::PerPage => Self::ERASE_SIZE,
+            AccessGranularity::PerBlock(block) => block,
+        };
+        (offset as usize + length).div_ceil(block) - offset as usize / block
+    }
+    /// Look up a non-empty read of `length` bytes at `offset` in the read
+    /// cache and return whether it is a hit. A miss caches the lines.
+    ///
+    /// Always a miss without a read cache, and not counted as one.
+    fn read_cache_lookup(&mut self, offset: u32, length: usize) -> bool {
+        let Some(cache) = self.read_cache.as_mut() else {
+            return false;
+        };
+        let lines =
+            offset as usize / cache.line_size..(offset as usize + length).div_ceil(cache.line_size);
+        let hit = lines.clone().all(|line| cache.cached.contains(&line));
+        for line in lines {
+            cache.cached.retain(|&cached| cached != line);
+            if cache.cached.len() == cache.lines {
+                cache.cached.remove(0);
+            }
+            cache.cached.push(line);
+        }
+        if hit {
+            self.read_cache_hits = self.read_cache_hits.saturating_add(1);
+        } else {
+            self.read_cache_misses = self.read_cache_misses.saturating_add(1);
+        }
+        hit
+    }
+    /// Drop the cached lines overlapping the byte `range`.
+    fn invalidate_read_cache(&mut self, range: Range<usize>) {
+        if let Some(cache) = self.read_cache.as_mut() {
+            let lines = range.start / cache.line_size..range.end.div_ceil(cache.line_size);
+            cache.cached.retain(|line| !lines.contains(line));
+        }
+    }
     /// Number of erase units erased since last stats reset.
+    ///
+    /// A single erase command spanning several pages counts each page.
     pub fn pages_erased(&self) -> usize {
-        self.erased / Self::ERASE_SIZE
+        self.erased_pages
     }
     /// Number of times [`start_operation`] was called.
     pub fn total_operations(&self) -> usize {
         self.total_operations
     }
+    /// Tags passed to [`start_operation`] since last stats reset, in order.
+    ///
+    /// Only recorded with
+    /// [`with_operation_history`](SimulatedNorFlashBuilder::with_operation_history),
+    /// otherwise empty. Together with
+    /// [`last_operation_stats`](Self::last_operation_stats) this gives a
+    /// timeline of the high-level actions:
+    ///
+    /// ```
+    /// use embedded_storage_sim::{SimulatedNorFlash, SimulatedNorFlashBuilder};
+    ///
+    /// let mut flash: SimulatedNorFlash<&str> = SimulatedNorFlashBuilder::new(4096)
+    ///     .with_operation_history(true)
+    ///     .build();
+    /// flash.start_operation("store");
+    /// flash.start_operation("fetch");
+    /// flash.start_operation("store");
+    /// assert_eq!(flash.operation_history(), ["store", "fetch", "store"]);
+    /// ```
+    ///
+    /// [`start_operation`]: Self::start_operation
+    pub fn operation_history(&self) -> &[O] {
+        &self.operation_history
+    }
+    /// Each entry of [`operation_history`](Self::operation_history) with the
+    /// range of [`transactions`](Self::transactions) it caused.
+    ///
+    /// A range ends at the next [`start_operation`] or [`end_operation`];
+    /// the current operation's range extends to the end of the log.
+    ///
+    /// ```
+    /// use embedded_storage::nor_flash::NorFlash;
+    /// use embedded_storage_sim::{SimulatedNorFlash, SimulatedNorFlashBuilder, TransactionLogLevel};
+    ///
+    /// let mut flash: SimulatedNorFlash<&str> = SimulatedNorFlashBuilder::new(4096)
+    ///     .with_logging(TransactionLogLevel::Minimal)
+    ///     .with_operation_history(true)
+    ///     .build();
+    /// flash.start_operation("format");
+    /// flash.erase(0, 4096).unwrap();
+    /// flash.start_operation("store");
+    /// flash.write(0, &[0; 4]).unwrap();
+    /// flash.write(4, &[0; 4]).unwrap();
+    /// flash.end_operation();
+    /// flash.write(8, &[0; 4]).unwrap();
+    /// assert_eq!(
+    ///     flash.operation_transaction_ranges(),
+    ///     [("format", 0..1), ("store", 1..3)]
+    /// );
+    /// ```
+    ///
+    /// [`start_operation`]: Self::start_operation
+    /// [`end_operation`]: Self::end_operation
+    pub fn operation_transaction_ranges(&self) -> Vec<(O, Range<usize>)> {
+        let len = self.transactions.len();
+        self.operation_history
+            .iter()
+            .cloned()
+            .zip(self.operation_transactions.iter().map(|range| {
+                // the current operation is still open
+                let end = if range.end == usize::MAX {
+                    len
+                } else {
+                    range.end
+                };
+                range.start.min(len)..end.min(len)
+            }))
+            .collect()
+    }
     /// Total number of storage accesses (reads + writes + erases).
     pub fn total_accesses(&self) -> usize {
-        self.read_accesses + self.write_accesses + self.erase_accesses
+        self.read_accesses
+            .saturating_add(self.write_accesses)
+            .saturating_add(self.erase_accesses)
     }
     /// Estimate the time spent reading based on accumulated stats.
     pub fn read_time(&self, timings: &FlashTimings) -> fugit::NanosDurationU64 {
@@ -364,25 +2776,953 @@ impl<O: Clone, const RS: usize, const WS: usize, const ES: usize> SimulatedNorFl
     }
     /// Estimate the time spent erasing based on accumulated stats.
     pub fn erase_time(&self, timings: &FlashTimings) -> fugit::MillisDurationU64 {
-        timings.erase_time(self.erased / Self::ERASE_SIZE, self.erase_accesses as u32)
+        timings.erase_time(self.erased_pages, self.erase_accesses as u32)
+            + timings.subsector_erase_time(self.erased_subsectors)
     }
     /// Estimate total time across all operations based on stats.
     pub fn total_time(&self, timings: &FlashTimings) -> fugit::MillisDurationU64 {
         timings.total_time(&self.stats()).convert()
     }
+    /// Call `step` until it returns `false`, failing once the accesses it
+    /// made take longer than `budget` in simulated time.
+    ///
+    /// The time is estimated with `timings` from the stats added since the
+    /// call, and checked after every step. Returns the simulated time spent.
+    /// Test a deadline-sensitive routine against the timing model:
+    ///
+    /// ```
+    /// use embedded_storage::nor_flash::NorFlash;
+    /// use embedded_storage_sim::{FlashTimings, SimulatedNorFlash, SpiType};
+    /// use fugit::{MillisDurationU64, RateExtU64};
+    ///
+    /// let timings = FlashTimings::new(SpiType::QSPI, false, 50.MHz(), MillisDurationU64::millis(40), 0);
+    /// let mut flash: SimulatedNorFlash = SimulatedNorFlash::new(8 * 4096);
+    /// let budget = MillisDurationU64::millis(100);
+    ///
+    /// let mut page = 0;
+    /// let error = flash
+    ///     .run_within(budget, &timings, |flash| {
+    ///         flash.erase(page * 4096, (page + 1) * 4096).unwrap();
+    ///         page += 1;
+    ///         page < 8
+    ///     })
+    ///     .unwrap_err();
+    /// assert_eq!(error.steps, 3);
+    /// assert_eq!(error.elapsed.to_millis(), 120);
+    ///
+    /// let elapsed = flash
+    ///     .run_within(budget, &timings, |flash| {
+    ///         flash.write(0, &[0; 256]).unwrap();
+    ///         false
+    ///     })
+    ///     .unwrap();
+    /// assert!(elapsed < budget);
+    /// ```
+    pub fn run_within(
+        &mut self,
+        budget: fugit::MillisDurationU64,
+        timings: &FlashTimings,
+        mut step: impl FnMut(&mut Self) -> bool,
+    ) -> Result<fugit::NanosDurationU64, DeadlineExceeded> {
+        let start = self.stats();
+        let mut steps = 0;
+        loop {
+            let more = step(self);
+            steps += 1;
+            let elapsed = timings.total_time(&(&self.stats() - &start));
+            if elapsed > budget {
+                return Err(DeadlineExceeded { elapsed, steps });
+            }
+            if !more {
+                return Ok(elapsed);
+            }
+        }
+    }
+    /// Flip `count` distinct random bits within `range` of the stored data.
+    ///
+    /// Unlike stuck bits these are one-time flips, which are lost on the
+    /// next erase. The bits are chosen with the failure injection RNG, so a
+    /// fixed seed corrupts the same bits every run. A
+    /// [`Transaction::Corruption`] is recorded unless logging is disabled.
+    ///
+    /// Without stored contents (see
+    /// [`with_metadata_only`](SimulatedNorFlashBuilder::with_metadata_only))
+    /// only the transaction is recorded.
+    ///
+    /// Panics if `range` is out of bounds or holds fewer than `count` bits.
+    pub fn corrupt_bits(&mut self, range: Range<u32>, count: usize) {
+        assert!(range.end as usize <= self.size());
+        assert!(count <= range.len() * 8);
+        if !self.metadata_only {
+            let bytes = &mut self.data[range.start as usize..range.end as usize];
+            for bit in index::sample(&mut self.rng, bytes.len() * 8, count) {
+                bytes[bit / 8] ^= 1 << (bit % 8);
+            }
+        }
+        self.update_sector_crcs(range.start as usize..range.end as usize);
+        self.invalidate_read_cache(range.start as usize..range.end as usize);
+        if self.log_level != TransactionLogLevel::None {
+            self.record(Transaction::corruption(
+                range.start,
+                range.end,
+                count,
+                self.current_operation.clone(),
+            ));
+        }
+    }
+    /// Record a [`Transaction::Marker`] with `label` in the transaction log.
+    ///
+    /// NOR flash has no flush, but storage layers often sync at commit
+    /// boundaries. Markers delimit those in the log. They are recorded at
+    /// every log level except [`TransactionLogLevel::None`] and don't affect
+    /// statistics.
+    pub fn mark(&mut self, label: &str) {
+        if self.log_level != TransactionLogLevel::None {
+            self.record(Transaction::marker(label, self.current_operation.clone()));
+        }
+    }
+    /// Revert the last `n` logged writes and erases and return how many were undone.
+    ///
+    /// Each one is removed from the transaction log and its captured
+    /// pre-image is copied back into the stored contents. That sets bits
+    /// back to 1 without an erase, which NOR flash can't do, so this is a
+    /// debugging aid ("step back") rather than a device operation: it
+    /// bypasses wear and stat tracking, the failure model and the
+    /// uninitialized and rewrite tracking. Reads, markers and rejected
+    /// accesses logged after the undone entries are dropped as well.
+    ///
+    /// Pre-images are only captured at [`TransactionLogLevel::Full`]
+    /// (writes also at [`TransactionLogLevel::WriteBeforeAfter`]). Undoing
+    /// stops early at an entry without one and at a
+    /// [`Transaction::Corruption`], as well as when the log is empty, e.g.
+    /// because a transaction sink is set.
+    ///
+    /// ```
+    /// use embedded_storage::nor_flash::NorFlash;
+    /// use embedded_storage_sim::{SimulatedNorFlash, SimulatedNorFlashBuilder, TransactionLogLevel};
+    ///
+    /// let mut flash: SimulatedNorFlash = SimulatedNorFlashBuilder::new(4096)
+    ///     .with_logging(TransactionLogLevel::Full)
+    ///     .build();
+    /// flash.write(0, b"first").unwrap();
+    /// let hash = flash.content_hash();
+    /// flash.erase(0, 4096).unwrap();
+    /// flash.write(0, b"second").unwrap();
+    /// assert_eq!(flash.undo_last(2), 2);
+    /// assert_eq!(flash.content_hash(), hash);
+    /// assert_eq!(flash.transactions().len(), 1);
+    /// ```
+    ///
+    /// Cursors from [`transactions_since`](Self::transactions_since) never
+    /// see an undone entry twice, and entries recorded after the undo are
+    /// still returned to them:
+    ///
+    /// ```
+    /// use embedded_storage::nor_flash::NorFlash;
+    /// use embedded_storage_sim::{SimulatedNorFlash, SimulatedNorFlashBuilder, TransactionLogLevel};
+    ///
+    /// let mut flash: SimulatedNorFlash = SimulatedNorFlashBuilder::new(4096)
+    ///     .with_logging(TransactionLogLevel::Full)
+    ///     .build();
+    /// flash.write(0, b"a").unwrap();
+    /// let (_, early) = flash.transactions_since(0);
+    /// flash.write(1, b"b").unwrap();
+    /// flash.write(2, b"c").unwrap();
+    /// let (_, late) = flash.transactions_since(early);
+    /// assert_eq!(late, 3);
+    ///
+    /// assert_eq!(flash.undo_last(2), 2);
+    /// flash.write(1, b"d").unwrap();
+    /// let (new, cursor) = flash.transactions_since(late);
+    /// assert_eq!((new.len(), cursor), (1, 4));
+    /// let (new, _) = flash.transactions_since(early);
+    /// assert_eq!(new.len(), 1);
+    /// ```
+    pub fn undo_last(&mut self, n: usize) -> usize {
+        let before = self.transactions.len();
+        let mut undone = 0;
+        while undone < n {
+            let (range, pre_image) = match self.transactions.last() {
+                Some(Transaction::Write {
+                    offset,
+                    length,
+                    before_write: Some(before_write),
+                    ..
+                }) => (*offset as usize..*offset as usize + length, before_write),
+                Some(Transaction::Erase {
+                    from,
+                    to,
+                    data: Some(data),
+                    ..
+                }) => (*from as usize..*to as usize, data),
+                Some(
+                    Transaction::Read { .. }
+                    | Transaction::Marker { .. }
+                    | Transaction::Rejected { .. },
+                ) => {
+                    self.transactions.pop();
+                    continue;
+                }
+                _ => break,
+            };
+            // without stored contents there is nothing to restore
+            if let Some(data) = self.data.get_mut(range.clone())
+                && data.len() == pre_image.len()
+            {
+                data.copy_from_slice(pre_image);
+                self.update_sector_crcs(range.clone());
+                self.invalidate_read_cache(range);
+            }
+            self.transactions.pop();
+            undone += 1;
+        }
+        let len = self.transactions.len();
+        if len < before {
+            let skipped = self.transactions_skipped() + (before - len);
+            self.transactions_undone.retain(|&(index, _)| index < len);
+            self.transactions_undone.push((len, skipped));
+            for range in &mut self.operation_transactions {
+                range.start = range.start.min(len);
+                // the current operation is still open
+                if range.end != usize::MAX {
+                    range.end = range.end.min(len);
+                }
+            }
+        }
+        undone
+    }
+    /// Cursor positions given up by [`undo_last`](Self::undo_last) so far.
+    fn transactions_skipped(&self) -> usize {
+        self.transactions_undone
+            .last()
+            .map_or(0, |&(_, skipped)| skipped)
+    }
+    /// Append `transaction` to the log, or hand it to the streaming sink if one is set.
+    fn record(&mut self, transaction: Transaction<O>) {
+        #[cfg(feature = "std")]
+        if let Some(sink) = self.transaction_sink.as_mut() {
+            if writeln!(sink.writer, "{}", (sink.to_json)(&transaction)).is_err() {
+                self.transaction_sink_errors = self.transaction_sink_errors.saturating_add(1);
+            }
+            return;
+        }
+        self.transactions.push(transaction);
+    }
     /// View the recorded transaction log.
     pub fn transactions(&self) -> &[Transaction<O>] {
         &self.transactions
     }
+    /// Flush the writer set with [`set_jsonl_writer`](Self::set_jsonl_writer), if any.
+    #[cfg(feature = "std")]
+    pub fn flush_jsonl_writer(&mut self) -> std::io::Result<()> {
+        match self.transaction_sink.as_mut() {
+            Some(sink) => sink.writer.flush(),
+            None => Ok(()),
+        }
+    }
+    /// Stop streaming and keep transactions in the log again.
+    ///
+    /// Returns the writer set with [`set_jsonl_writer`](Self::set_jsonl_writer)
+    /// after flushing it, `None` if none was set. A failed flush is counted
+    /// in [`jsonl_write_errors`](Self::jsonl_write_errors).
+    #[cfg(feature = "std")]
+    pub fn clear_jsonl_writer(&mut self) -> Option<Box<dyn std::io::Write + Send>> {
+        if self.flush_jsonl_writer().is_err() {
+            self.transaction_sink_errors = self.transaction_sink_errors.saturating_add(1);
+        }
+        self.transaction_sink.take().map(|sink| sink.writer)
+    }
+    /// Number of transactions (and flushes) that failed to reach the
+    /// [`set_jsonl_writer`](Self::set_jsonl_writer) writer.
+    #[cfg(feature = "std")]
+    pub fn jsonl_write_errors(&self) -> usize {
+        self.transaction_sink_errors
+    }
+    /// Transactions recorded since `cursor`, and the cursor to pass next time.
+    ///
+    /// Start with a cursor of `0`. Cursors count every transaction ever
+    /// recorded, so they stay valid when the log is cleared by
+    /// [`reset_stats`](Self::reset_stats): entries that were cleared before
+    /// they were polled are skipped, and so are entries removed by
+    /// [`undo_last`](Self::undo_last). Transactions streamed to a writer
+    /// instead of the log are never returned.
+    ///
+    /// ```
+    /// use embedded_storage::nor_flash::NorFlash;
+    /// use embedded_storage_sim::{SimulatedNorFlash, SimulatedNorFlashBuilder, TransactionLogLevel};
+    ///
+    /// let mut flash: SimulatedNorFlash = SimulatedNorFlashBuilder::new(4096)
+    ///     .with_logging(TransactionLogLevel::Minimal)
+    ///     .build();
+    /// flash.write(0, &[0]).unwrap();
+    /// let (new, cursor) = flash.transactions_since(0);
+    /// assert_eq!((new.len(), cursor), (1, 1));
+    ///
+    /// flash.write(1, &[0]).unwrap();
+    /// flash.reset_stats();
+    /// flash.write(2, &[0]).unwrap();
+    /// let (new, cursor) = flash.transactions_since(cursor);
+    /// assert_eq!((new.len(), cursor), (1, 3));
+    /// ```
+    pub fn transactions_since(&self, cursor: usize) -> (&[Transaction<O>], usize) {
+        let position = cursor.saturating_sub(self.transactions_dropped);
+        let mut start = position;
+        for &(index, skipped) in &self.transactions_undone {
+            if position < index + skipped {
+                // the cursor points into entries that were undone
+                start = start.min(index);
+                break;
+            }
+            start = position - skipped;
+        }
+        let new = self.transactions.get(start..).unwrap_or_default();
+        let next = self
+            .transactions_dropped
+            .saturating_add(self.transactions.len())
+            .saturating_add(self.transactions_skipped());
+        (new, next)
+    }
+    /// Recorded transactions that were tagged with operation `op`.
+    pub fn transactions_for<'a>(&'a self, op: &'a O) -> impl Iterator<Item = &'a Transaction<O>>
+    where
+        O: PartialEq,
+    {
+        self.transactions
+            .iter()
+            .filter(move |transaction| transaction.operation() == Some(op))
+    }
+    /// Recorded transactions of the given `kind`.
+    pub fn transactions_of_kind(
+        &self,
+        kind: TransactionKind,
+    ) -> impl Iterator<Item = &Transaction<O>> {
+        self.transactions
+            .iter()
+            .filter(move |transaction| transaction.kind() == kind)
+    }
+    /// The transaction log with an estimated duration for each entry.
+    ///
+    /// Reads and writes are timed from their byte count plus their access
+    /// overheads (see [`AccessGranularity`]), erases from their page count
+    /// plus one command. An erase that isn't `ERASE_SIZE`-aligned is a
+    /// sub-sector erase and timed per sub-sector instead; one that covers
+    /// whole pages looks like a page erase in the log and is timed as one.
+    /// Zero-length operations, corruptions, markers and rejected accesses
+    /// take no time, so with reads logged (any level
+    /// above [`TransactionLogLevel::Minimal`]) the entries add up to
+    /// [`FlashTimings::total_time`] of the [`stats`](Self::stats):
+    ///
+    /// ```
+    /// use embedded_storage::nor_flash::{NorFlash, ReadNorFlash};
+    /// use embedded_storage_sim::{
+    ///     FlashTimings, SimulatedNorFlash, SimulatedNorFlashBuilder, SpiType, TransactionLogLevel,
+    /// };
+    /// use fugit::{ExtU64, RateExtU64};
+    ///
+    /// let mut flash: SimulatedNorFlash = SimulatedNorFlashBuilder::new(4 * 4096)
+    ///     .with_subsector_size(1024)
+    ///     .with_logging(TransactionLogLevel::WriteDataOnly)
+    ///     .build();
+    /// let timings = FlashTimings::builder(SpiType::QSPI, false, 100.MHz(), 45.millis())
+    ///     .with_access_overhead_cycles(40)
+    ///     .with_subsector_erase_time(12.millis())
+    ///     .with_erase_busy_time(300.micros())
+    ///     .build();
+    /// flash.erase(0, 2 * 4096).unwrap();
+    /// flash.erase_subsector(9 * 1024, 11 * 1024).unwrap();
+    /// flash.write(9 * 1024, &[0; 100]).unwrap();
+    /// let mut buf = [0; 64];
+    /// flash.read(0, &mut buf).unwrap();
+    ///
+    /// let entries = flash.transactions_with_timings(&timings);
+    /// assert_eq!(entries[1].1.to_nanos(), 2 * 12_000_000 + 300_000 + 400);
+    /// let sum = entries.iter().map(|(_, time)| time.ticks()).sum::<u64>();
+    /// assert_eq!(sum, timings.total_time(&flash.stats()).ticks());
+    /// ```
+    pub fn transactions_with_timings(
+        &self,
+        timings: &FlashTimings,
+    ) -> Vec<(Transaction<O>, fugit::NanosDurationU64)> {
+        let accesses = |length: usize| u32::from(length != 0);
+        // reads and writes are counted per the configured access granularity
+        let transfers = |offset: u32, length: usize| match length {
+            0 => 0,
+            _ => self.accesses(offset, length) as u32,
+        };
+        self.transactions
+            .iter()
+            .map(|transaction| {
+                let duration = match *transaction {
+                    Transaction::Read { offset, length, .. } => {
+                        timings.read_time(length, transfers(offset, length))
+                    }
+                    Transaction::Write { offset, length, .. } => {
+                        timings.write_time(length, transfers(offset, length))
+                    }
+                    Transaction::Erase { from, to, .. } => {
+                        let length = (to - from) as usize;
+                        let aligned = [from, to]
+                            .iter()
+                            .all(|&offset| (offset as usize).is_multiple_of(Self::ERASE_SIZE));
+                        if self.subsector_size != 0 && !aligned {
+                            timings.erase_time_nanos(0, accesses(length))
+                                + timings
+                                    .subsector_erase_time(length / self.subsector_size)
+                                    .convert()
+                        } else {
+                            timings.erase_time_nanos(length / Self::ERASE_SIZE, accesses(length))
+                        }
+                    }
+                    Transaction::Corruption { .. }
+                    | Transaction::Marker { .. }
+                    | Transaction::Rejected { .. } => fugit::NanosDurationU64::from_ticks(0),
+                };
+                (transaction.clone(), duration)
+            })
+            .collect()
+    }
     /// Per-page erase cycle counters for wear analysis.
     pub fn page_erase_cycles(&self) -> &[u32] {
         &self.page_cycles
     }
+    /// The [`total_operations`](Self::total_operations) count at the last
+    /// erase of each page, `None` if it wasn't erased since the last stats
+    /// reset.
+    ///
+    /// Together with [`page_erase_cycles`](Self::page_erase_cycles) this
+    /// shows erase recency, e.g. whether wear leveling picks the least
+    /// recently erased pages. Erases before the first
+    /// [`start_operation`](Self::start_operation) are at operation `0`, and
+    /// a sub-sector erase counts for its page.
+    ///
+    /// ```
+    /// use embedded_storage::nor_flash::NorFlash;
+    /// use embedded_storage_sim::SimulatedNorFlash;
+    ///
+    /// let mut flash: SimulatedNorFlash<&str> = SimulatedNorFlash::new(4 * 4096);
+    /// flash.erase(0, 4096).unwrap();
+    /// for page in [2, 3, 2] {
+    ///     flash.start_operation("compact");
+    ///     flash.erase(page * 4096, (page + 1) * 4096).unwrap();
+    /// }
+    /// assert_eq!(flash.page_last_erased_op(), [Some(0), None, Some(3), Some(2)]);
+    /// ```
+    pub fn page_last_erased_op(&self) -> &[Option<usize>] {
+        &self.page_last_erased_op
+    }
+    /// Per-sub-sector erase cycle counters, empty without
+    /// [`with_subsector_size`](SimulatedNorFlashBuilder::with_subsector_size).
+    ///
+    /// Full page erases count for every sub-sector of the page.
+    pub fn subsector_erase_cycles(&self) -> &[u32] {
+        &self.subsector_cycles
+    }
+    /// Range of [`subsector_erase_cycles`](Self::subsector_erase_cycles)
+    /// covering page `page_index`, empty without sub-sectors.
+    fn subsectors_of_page(&self, page_index: usize) -> Range<usize> {
+        if self.subsector_size == 0 {
+            return 0..0;
+        }
+        let per_page = Self::ERASE_SIZE / self.subsector_size;
+        page_index * per_page..(page_index + 1) * per_page
+    }
+    /// Remaining safe erase cycles, based on each page's [`page_endurance`].
+    ///
+    /// Pages past their limit count as zero remaining cycles.
+    ///
+    /// [`page_endurance`]: Self::page_endurance
+    pub fn endurance_remaining(&self) -> EnduranceReport {
+        let remaining = |page: usize| {
+            self.page_endurance(page)
+                .saturating_sub(self.page_cycles[page])
+        };
+        let (worst_page_index, worst_page_remaining) = (0..self.page_count())
+            .map(|page| (page, remaining(page)))
+            .min_by_key(|&(_, remaining)| remaining)
+            .unwrap_or((0, 0));
+        EnduranceReport {
+            total_remaining: (0..self.page_count())
+                .map(|page| remaining(page) as u64)
+                .sum(),
+            worst_page_remaining,
+            worst_page_index,
+        }
+    }
+    /// How close the pages are to their [`page_endurance`], as a figure of
+    /// merit that is comparable across storage layers and flash sizes.
+    ///
+    /// ```
+    /// use embedded_storage::nor_flash::NorFlash;
+    /// use embedded_storage_sim::{SimulatedNorFlash, SimulatedNorFlashBuilder};
+    ///
+    /// let mut flash: SimulatedNorFlash = SimulatedNorFlashBuilder::new(4 * 4096)
+    ///     .with_minimum_erase_cycles(100)
+    ///     .build();
+    /// for _ in 0..50 {
+    ///     flash.erase(0, 4096).unwrap();
+    /// }
+    /// for _ in 0..150 {
+    ///     flash.erase(4096, 2 * 4096).unwrap();
+    /// }
+    /// let score = flash.wear_score();
+    /// assert_eq!(score.max_pressure, 1.5);
+    /// assert_eq!(score.average_pressure, (0.5 + 1.0) / 4.0);
+    /// ```
+    ///
+    /// Pages with an endurance of zero count as one cycle of endurance.
+    ///
+    /// [`page_endurance`]: Self::page_endurance
+    pub fn wear_score(&self) -> WearScore {
+        let pressure =
+            |page: usize| self.page_cycles[page] as f64 / self.page_endurance(page).max(1) as f64;
+        let pages = self.page_count();
+        WearScore {
+            max_pressure: (0..pages).map(pressure).fold(0.0, f64::max),
+            average_pressure: (0..pages).map(|page| pressure(page).min(1.0)).sum::<f64>()
+                / pages.max(1) as f64,
+        }
+    }
+    /// Iterate over all pages with their wear and defect state.
+    ///
+    /// This is a typed view over [`page_erase_cycles`](Self::page_erase_cycles)
+    /// and the injected stuck bits, intended for reports and UIs.
+    pub fn pages(&self) -> impl Iterator<Item = PageInfo> + '_ {
+        self.page_cycles
+            .iter()
+            .enumerate()
+            .map(|(index, &erase_cycles)| {
+                let range = index * Self::ERASE_SIZE..(index + 1) * Self::ERASE_SIZE;
+                let stuck_bit_count = self
+                    .stuck_at_0_bits
+                    .get(range.clone())
+                    .unwrap_or_default()
+                    .iter()
+                    .zip(self.stuck_at_1_bits.get(range).unwrap_or_default())
+                    .map(|(stuck_at_0, stuck_at_1)| (stuck_at_0 | stuck_at_1).count_ones() as usize)
+                    .sum();
+                PageInfo {
+                    index,
+                    erase_cycles,
+                    stuck_bit_count,
+                }
+            })
+    }
+    /// All stuck bits, sorted by offset and bit position.
+    ///
+    /// The order is deterministic, so the list can be compared against
+    /// golden files. A bit that is stuck both ways reads as 0 and is
+    /// reported once as [`FailureKind::StuckAt0`]. Always empty without
+    /// stored contents.
+    ///
+    /// ```
+    /// use embedded_storage::nor_flash::NorFlash;
+    /// use embedded_storage_sim::{FailureKind, SimulatedNorFlash, SimulatedNorFlashBuilder, StuckBit};
+    ///
+    /// let mut flash: SimulatedNorFlash = SimulatedNorFlashBuilder::new(4 * 4096)
+    ///     .with_minimum_erase_cycles(0)
+    ///     .with_failure_rate(1)
+    ///     .with_rng_seed(7)
+    ///     .build();
+    /// for _ in 0..8 {
+    ///     flash.erase(0, 4 * 4096).unwrap();
+    /// }
+    /// let both_ways = [FailureKind::StuckAt0, FailureKind::StuckAt1]
+    ///     .map(|kind| StuckBit { offset: 5, bit: 3, kind });
+    /// flash.apply_defect_map(&both_ways);
+    /// let stuck_bits = flash.stuck_bits();
+    /// assert!(!stuck_bits.is_empty());
+    /// assert!(stuck_bits.is_sorted_by_key(|stuck| (stuck.offset, stuck.bit)));
+    /// assert_eq!(flash.defect_density_per_page().iter().sum::<usize>(), stuck_bits.len());
+    /// ```
+    pub fn stuck_bits(&self) -> Vec<StuckBit> {
+        let mut stuck_bits = Vec::new();
+        for (offset, (&stuck_at_0, &stuck_at_1)) in self
+            .stuck_at_0_bits
+            .iter()
+            .zip(&self.stuck_at_1_bits)
+            .enumerate()
+        {
+            if stuck_at_0 | stuck_at_1 == 0 {
+                continue;
+            }
+            for bit in 0..8 {
+                let kind = if stuck_at_0 & 1 << bit != 0 {
+                    FailureKind::StuckAt0
+                } else if stuck_at_1 & 1 << bit != 0 {
+                    FailureKind::StuckAt1
+                } else {
+                    continue;
+                };
+                stuck_bits.push(StuckBit {
+                    offset: offset as u32,
+                    bit,
+                    kind,
+                });
+            }
+        }
+        stuck_bits
+    }
+    /// Make the given bits stuck, bypassing the failure model.
+    ///
+    /// Existing stuck bits are kept and the
+    /// [`failure callback`](Self::set_failure_callback) is not called.
+    /// Applying the [`stuck_bits`](Self::stuck_bits) of one flash to a fresh
+    /// one replays its defects exactly:
+    ///
+    /// ```
+    /// use embedded_storage::nor_flash::NorFlash;
+    /// use embedded_storage_sim::{FailureKind, SimulatedNorFlash, StuckBit};
+    ///
+    /// let mut flash: SimulatedNorFlash = SimulatedNorFlash::new(4096);
+    /// flash.apply_defect_map(&[
+    ///     StuckBit { offset: 3, bit: 0, kind: FailureKind::StuckAt0 },
+    ///     StuckBit { offset: 8, bit: 7, kind: FailureKind::StuckAt1 },
+    /// ]);
+    /// assert_eq!(flash.effective_byte(3), 0xfe);
+    /// flash.write(8, &[0x00; 4]).unwrap();
+    /// assert_eq!(flash.effective_byte(8), 0x80);
+    ///
+    /// let mut replay: SimulatedNorFlash = SimulatedNorFlash::new(4096);
+    /// replay.apply_defect_map(&flash.stuck_bits());
+    /// assert_eq!(replay.stuck_bits(), flash.stuck_bits());
+    /// ```
+    ///
+    /// Does nothing without stored contents. Panics if an offset is out of
+    /// bounds or a bit position is above 7.
+    pub fn apply_defect_map(&mut self, defects: &[StuckBit]) {
+        if self.metadata_only {
+            return;
+        }
+        for defect in defects {
+            assert!(defect.bit < 8);
+            let masks = match defect.kind {
+                FailureKind::StuckAt0 => &mut self.stuck_at_0_bits,
+                FailureKind::StuckAt1 => &mut self.stuck_at_1_bits,
+            };
+            masks[defect.offset as usize] |= 1 << defect.bit;
+            let offset = defect.offset as usize;
+            self.invalidate_read_cache(offset..offset + 1);
+        }
+    }
+    /// Number of stuck bits per page, indexed like
+    /// [`page_erase_cycles`](Self::page_erase_cycles).
+    ///
+    /// Same as [`PageInfo::stuck_bit_count`] from [`pages`](Self::pages).
+    pub fn defect_density_per_page(&self) -> Vec<usize> {
+        self.pages().map(|page| page.stuck_bit_count).collect()
+    }
+    /// Indices of the pages erased more than `threshold` times.
+    ///
+    /// Handy for assertions like "no page exceeded 90% of its endurance":
+    ///
+    /// ```
+    /// use embedded_storage::nor_flash::NorFlash;
+    /// use embedded_storage_sim::{SimulatedNorFlash, SimulatedNorFlashBuilder};
+    ///
+    /// let mut flash: SimulatedNorFlash = SimulatedNorFlashBuilder::new(4 * 4096)
+    ///     .with_minimum_erase_cycles(100)
+    ///     .build();
+    /// for _ in 0..95 {
+    ///     flash.erase(4096, 2 * 4096).unwrap();
+    /// }
+    /// assert_eq!(flash.pages_above_cycles(flash.page_endurance(1) * 9 / 10), [1]);
+    /// assert!(flash.pages_with_defects().is_empty());
+    /// ```
+    pub fn pages_above_cycles(&self, threshold: u32) -> Vec<usize> {
+        self.page_cycles
+            .iter()
+            .enumerate()
+            .filter(|&(_, &cycles)| cycles > threshold)
+            .map(|(index, _)| index)
+            .collect()
+    }
+    /// Indices of the pages with at least one stuck bit.
+    ///
+    /// Always empty without stored contents.
+    pub fn pages_with_defects(&self) -> Vec<usize> {
+        (0..self.page_count())
+            .filter(|&index| {
+                let range = index * Self::ERASE_SIZE..(index + 1) * Self::ERASE_SIZE;
+                let has_defect = |masks: &[u8]| {
+                    masks
+                        .get(range.clone())
+                        .is_some_and(|masks| masks.iter().any(|&mask| mask != 0))
+                };
+                has_defect(&self.stuck_at_0_bits) || has_defect(&self.stuck_at_1_bits)
+            })
+            .collect()
+    }
 }
 
+impl<O: Clone, const RS: usize, const WS: usize, const ES: usize> SimulatedNorFlash<O, RS, WS, ES> {
+    /// Attach an operation tag to the next transaction(s).
+    ///
+    /// Useful for correlating storage activity with high-level actions in
+    /// higher layers. The tag is stored in emitted [`Transaction`]s. A still
+    /// running operation is ended first, see [`end_operation`].
+    ///
+    /// Every transaction recorded afterwards carries the tag, including all
+    /// reads, writes and erases a storage layer performs internally, until
+    /// the next `start_operation` or [`end_operation`]:
+    ///
+    /// ```
+    /// use embedded_storage::nor_flash::{NorFlash, ReadNorFlash};
+    /// use embedded_storage_sim::{
+    ///     SimulatedNorFlash, SimulatedNorFlashBuilder, TransactionLogLevel,
+    /// };
+    ///
+    /// let mut flash: SimulatedNorFlash<&str> = SimulatedNorFlashBuilder::new(2 * 4096)
+    ///     .with_logging(TransactionLogLevel::WriteDataOnly)
+    ///     .build();
+    /// flash.start_operation("store");
+    /// let mut header = [0; 4];
+    /// flash.read(0, &mut header).unwrap();
+    /// flash.erase(4096, 8192).unwrap();
+    /// flash.write(4096, b"item").unwrap();
+    /// flash.end_operation();
+    /// flash.write(0, b"free").unwrap();
+    ///
+    /// assert_eq!(flash.transactions_for(&"store").count(), 3);
+    /// assert_eq!(flash.transactions().last().unwrap().operation(), None);
+    /// ```
+    ///
+    /// [`end_operation`]: Self::end_operation
+    pub fn start_operation(&mut self, operation: O) {
+        self.finish_operation_stats();
+        if let Some(sampler) = &self.snapshot_sampler
+            && self.total_operations != 0
+            && self.total_operations.is_multiple_of(sampler.interval)
+        {
+            let snapshot = (sampler.snapshot)(self);
+            if let Some(sampler) = self.snapshot_sampler.as_mut() {
+                (sampler.callback)(snapshot);
+            }
+        }
+        if self.record_operation_history {
+            self.operation_history.push(operation.clone());
+            self.operation_transactions
+                .push(self.transactions.len()..usize::MAX);
+        }
+        self.current_operation = Some(operation);
+        self.total_operations = self.total_operations.saturating_add(1);
+        self.operation_start_stats = self.stats();
+    }
+    /// End the current operation.
+    ///
+    /// Its stats are added to [`stats_by_operation`] and subsequent
+    /// transactions are untagged until the next [`start_operation`].
+    ///
+    /// [`stats_by_operation`]: Self::stats_by_operation
+    /// [`start_operation`]: Self::start_operation
+    pub fn end_operation(&mut self) {
+        self.finish_operation_stats();
+        self.current_operation = None;
+    }
+    /// Sleep for the estimated duration of every operation, multiplied by
+    /// `scale`, when it ends.
+    ///
+    /// Slows workloads down to watch a live visualization. The duration is
+    /// [`FlashTimings::total_time`] of the operation's stats, so a `scale`
+    /// of `1.0` plays back at the speed of the real part and `0.1` ten times
+    /// faster. `None` turns playback off again, which is the default.
+    ///
+    /// Only operations sleep: the pause happens when an operation ends, via
+    /// [`end_operation`](Self::end_operation) or the next
+    /// [`start_operation`](Self::start_operation), and covers all of its
+    /// accesses at once. Accesses outside of an operation are never
+    /// throttled, so wrap the workload's steps in operations to watch them.
+    ///
+    /// ```
+    /// use std::time::{Duration, Instant};
+    ///
+    /// use embedded_storage::nor_flash::NorFlash;
+    /// use embedded_storage_sim::{FlashTimings, SimulatedNorFlash, SpiType};
+    /// use fugit::{MillisDurationU64, RateExtU64};
+    ///
+    /// let timings = FlashTimings::new(SpiType::SPI, false, 8.MHz(), MillisDurationU64::millis(40), 0);
+    /// let mut flash: SimulatedNorFlash<&str> = SimulatedNorFlash::new(4096);
+    /// flash.set_realtime_playback(Some((timings, 0.5)));
+    /// let start = Instant::now();
+    /// flash.start_operation("erase");
+    /// flash.erase(0, 4096).unwrap();
+    /// flash.end_operation();
+    /// assert!(start.elapsed() >= Duration::from_millis(20));
+    /// ```
+    #[cfg(feature = "std")]
+    pub fn set_realtime_playback(&mut self, playback: Option<(FlashTimings, f32)>) {
+        self.realtime_playback = playback;
+    }
+    /// Start `operation` and end it when the returned guard is dropped.
+    ///
+    /// The guard derefs to the flash, so the operation's accesses go
+    /// through it. Unlike pairing [`start_operation`](Self::start_operation)
+    /// with [`end_operation`](Self::end_operation) by hand, early returns
+    /// and `?` can't leave the tag set.
+    ///
+    /// ```
+    /// use embedded_storage::nor_flash::NorFlash;
+    /// use embedded_storage_sim::SimulatedNorFlash;
+    ///
+    /// let mut flash: SimulatedNorFlash<&str> = SimulatedNorFlash::new(4096);
+    /// {
+    ///     let mut op = flash.operation("store");
+    ///     op.write(0, b"item").unwrap();
+    /// }
+    /// flash.write(4, b"free").unwrap();
+    /// assert_eq!(flash.stats_by_operation()[&"store"].bytes_written, 4);
+    /// ```
+    pub fn operation(&mut self, operation: O) -> OperationGuard<'_, O, RS, WS, ES> {
+        self.start_operation(operation);
+        OperationGuard { flash: self }
+    }
+    fn finish_operation_stats(&mut self) {
+        if self.current_operation.is_some()
+            && let Some(range) = self.operation_transactions.last_mut()
+        {
+            range.end = self.transactions.len();
+        }
+        if let Some(operation) = self.current_operation.clone() {
+            let delta = self.last_operation_stats();
+            #[cfg(feature = "std")]
+            if let Some((timings, scale)) = &self.realtime_playback {
+                let nanos = timings.total_time(&delta).ticks() as f64 * *scale as f64;
+                std::thread::sleep(std::time::Duration::from_nanos(nanos as u64));
+            }
+            self.operation_stats.push((operation, delta));
+        }
+    }
+}
+
+impl<O: Clone + Eq + Hash, const RS: usize, const WS: usize, const ES: usize>
+    SimulatedNorFlash<O, RS, WS, ES>
+{
+    /// Accumulated stats per operation tag.
+    ///
+    /// An operation's stats are added when it ends, either explicitly via
+    /// [`end_operation`](Self::end_operation) or implicitly when the next
+    /// operation is started. The running operation is not included yet.
+    ///
+    /// Tagging itself only needs `O: Clone`; each finished operation's
+    /// delta is kept and the map is built from them on every call.
+    pub fn stats_by_operation(&self) -> HashMap<O, FlashStats> {
+        let mut by_operation = HashMap::<O, FlashStats>::new();
+        for (operation, delta) in &self.operation_stats {
+            let stats = by_operation.entry(operation.clone()).or_default();
+            *stats = &*stats + delta;
+        }
+        by_operation
+    }
+}
+
+/// Geometry, stats, defect count and log state, without the contents.
+///
+/// The output stays small regardless of the flash size:
+///
+/// ```
+/// use embedded_storage_sim::SimulatedNorFlash;
+///
+/// let flash: SimulatedNorFlash = SimulatedNorFlash::new(1024 * 1024);
+/// let debug = format!("{flash:?}");
+/// assert!(debug.starts_with("SimulatedNorFlash { size: 1048576, read_size: 1,"));
+/// assert!(debug.len() < 1000);
+/// ```
+impl<O: Clone, const RS: usize, const WS: usize, const ES: usize> fmt::Debug
+    for SimulatedNorFlash<O, RS, WS, ES>
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let stuck_bits: usize = self
+            .stuck_at_0_bits
+            .iter()
+            .zip(&self.stuck_at_1_bits)
+            .map(|(stuck_at_0, stuck_at_1)| (stuck_at_0 | stuck_at_1).count_ones() as usize)
+            .sum();
+        f.debug_struct("SimulatedNorFlash")
+            .field("size", &self.size)
+            .field("read_size", &RS)
+            .field("write_size", &WS)
+            .field("erase_size", &ES)
+            .field("metadata_only", &self.metadata_only)
+            .field("ram_semantics", &self.ram_semantics)
+            .field("stats", &self.stats())
+            .field("stuck_bits", &stuck_bits)
+            .field("log_level", &self.log_level)
+            .field("transactions", &self.transactions.len())
+            .field("rng_seed", &self.rng_seed)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Guard returned by [`SimulatedNorFlash::operation`] that ends the
+/// operation on drop.
+pub struct OperationGuard<'a, O: Clone, const RS: usize, const WS: usize, const ES: usize> {
+    flash: &'a mut SimulatedNorFlash<O, RS, WS, ES>,
+}
+
+impl<O: Clone, const RS: usize, const WS: usize, const ES: usize> Deref
+    for OperationGuard<'_, O, RS, WS, ES>
+{
+    type Target = SimulatedNorFlash<O, RS, WS, ES>;
+
+    fn deref(&self) -> &Self::Target {
+        self.flash
+    }
+}
+
+impl<O: Clone, const RS: usize, const WS: usize, const ES: usize> DerefMut
+    for OperationGuard<'_, O, RS, WS, ES>
+{
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.flash
+    }
+}
+
+impl<O: Clone, const RS: usize, const WS: usize, const ES: usize> Drop
+    for OperationGuard<'_, O, RS, WS, ES>
+{
+    fn drop(&mut self) {
+        self.flash.end_operation();
+    }
+}
+
+mod banked;
+#[cfg(feature = "std")]
+mod benchmark;
 mod blocking;
+mod calibration;
+#[cfg(feature = "std")]
+mod codec;
+mod error;
+mod failure;
+mod heatmap;
+mod plan;
+#[cfg(feature = "std")]
+mod reader;
+#[cfg(feature = "retention")]
+mod retention;
+pub mod test_util;
 mod transaction;
-pub use transaction::{Transaction, TransactionLogLevel};
+pub use banked::BankedFlash;
+#[cfg(feature = "std")]
+pub use benchmark::{Benchmark, RunData};
+use calibration::Calibration;
+pub use calibration::{TimingError, TimingErrorReport};
+#[cfg(feature = "std")]
+pub use codec::decode_transactions;
+pub use error::FlashError;
+pub use failure::{
+    Defect, ErasedPage, FailureEvent, FailureKind, FailureModel, ProgramFailure, SimpleWearModel,
+    StuckBit,
+};
+use failure::{FailureModelFactory, FailureRng, FailureWeight};
+pub use heatmap::{wear_color, wear_color_scale};
+pub use plan::PlannedOp;
+#[cfg(feature = "std")]
+pub use reader::FlashReader;
+#[cfg(feature = "retention")]
+pub use retention::RetentionModel;
+pub use transaction::{
+    AccessKind, HEX_DUMP_LIMIT, Transaction, TransactionKind, TransactionLogLevel,
+};
 
 impl<O: Clone, const RS: usize, const WS: usize, const ES: usize> ReadNorFlash
     for SimulatedNorFlash<O, RS, WS, ES>
@@ -394,7 +3734,7 @@ impl<O: Clone, const RS: usize, const WS: usize, const ES: usize> ReadNorFlash
     }
 
     fn capacity(&self) -> usize {
-        self.data.len()
+        self.size()
     }
 }
 impl<O: Clone, const RS: usize, const WS: usize, const ES: usize> NorFlash
@@ -421,12 +3761,23 @@ impl<O: Clone, const RS: usize, const WS: usize, const ES: usize> MultiwriteNorF
 {
 }
 
+/// An erased 1 MiB flash for quick experiments.
+///
+/// The size is rounded down to whole erase units, but is at least one unit.
+impl<O: Clone, const RS: usize, const WS: usize, const ES: usize> Default
+    for SimulatedNorFlash<O, RS, WS, ES>
+{
+    fn default() -> Self {
+        Self::new((1024 * 1024 / ES).max(1) * ES)
+    }
+}
+
 /// Convenience alias: read alignment 1B, write alignment 1B, erase alignment 4KiB.
-pub type SimulatedNorFlashR1W1E4k<O> = SimulatedNorFlash<O, 1, 1, 4096>;
+pub type SimulatedNorFlashR1W1E4k<O = ()> = SimulatedNorFlash<O, 1, 1, 4096>;
 /// Convenience alias: read alignment 1B, write alignment 4B, erase alignment 4KiB (common for NOR flashes).
-pub type SimulatedNorFlashR1W4E4k<O> = SimulatedNorFlash<O, 1, 4, 4096>;
+pub type SimulatedNorFlashR1W4E4k<O = ()> = SimulatedNorFlash<O, 1, 4, 4096>;
 /// Convenience alias: read alignment 4B, write alignment 4B, erase alignment 4KiB.
-pub type SimulatedNorFlashR4W4E4k<O> = SimulatedNorFlash<O, 4, 4, 4096>;
+pub type SimulatedNorFlashR4W4E4k<O = ()> = SimulatedNorFlash<O, 4, 4, 4096>;
 
 /// Common dynamic wrapper for typical `READ_SIZE`/`WRITE_SIZE`/`ERASE_SIZE` combos.
 ///
@@ -459,6 +3810,16 @@ impl<O> From<SimulatedNorFlashR4W4E4k<O>> for AnySimulatedNorFlash<O> {
 pub struct FlashSnapshot {
     /// Full raw contents, if requested via [`SimulatedNorFlash::snapshot`].
     pub data: Option<Vec<u8>>,
+    /// [`SimulatedNorFlash::content_hash`], captured together with `data`.
+    pub content_hash: Option<u64>,
+    /// Total flash capacity in bytes.
+    pub capacity: usize,
+    /// Erase unit (page) size in bytes.
+    pub erase_size: usize,
+    /// Write alignment in bytes.
+    pub write_size: usize,
+    /// Read alignment in bytes.
+    pub read_size: usize,
     /// Per-page erase cycle counters.
     pub page_cycles: Vec<u32>,
     /// Amount of bytes read so far.
@@ -475,19 +3836,191 @@ pub struct FlashSnapshot {
     pub transactions_len: usize,
     /// The most recent operation tag, if any.
     pub last_operation: Option<String>,
+    /// Seed of the failure injection RNG.
+    pub rng_seed: u64,
+    /// Number of values drawn from the failure injection RNG.
+    pub rng_draws: u64,
+    /// Free-form provenance, e.g. the configuration and workload name. Set
+    /// via [`SimulatedNorFlash::snapshot_labeled`].
+    pub label: Option<String>,
+}
+
+impl FlashSnapshot {
+    /// Add the wear and counters of `other`, e.g. to aggregate several runs.
+    ///
+    /// `page_cycles` are summed per page, as are the byte, access, operation
+    /// and transaction counters. Contents, hash, last operation, RNG state
+    /// and label are kept from `self`.
+    ///
+    /// ```
+    /// use embedded_storage::nor_flash::NorFlash;
+    /// use embedded_storage_sim::SimulatedNorFlash;
+    ///
+    /// let mut first: SimulatedNorFlash<&str> = SimulatedNorFlash::new(2 * 4096);
+    /// first.erase(0, 4096).unwrap();
+    /// let mut second: SimulatedNorFlash<&str> = SimulatedNorFlash::new(2 * 4096);
+    /// second.erase(0, 2 * 4096).unwrap();
+    ///
+    /// let mut total = first.snapshot(false);
+    /// total += &second.snapshot(false);
+    /// assert_eq!(total.page_cycles, [2, 1]);
+    /// assert_eq!(total.pages_erased, 3);
+    /// ```
+    ///
+    /// Panics if the snapshots differ in capacity or geometry.
+    pub fn accumulate(&mut self, other: &FlashSnapshot) {
+        assert_eq!(
+            (
+                self.capacity,
+                self.erase_size,
+                self.write_size,
+                self.read_size
+            ),
+            (
+                other.capacity,
+                other.erase_size,
+                other.write_size,
+                other.read_size
+            ),
+            "snapshots of different flash geometries"
+        );
+        for (cycles, other) in self.page_cycles.iter_mut().zip(&other.page_cycles) {
+            *cycles = cycles.saturating_add(*other);
+        }
+        self.bytes_read = self.bytes_read.saturating_add(other.bytes_read);
+        self.bytes_written = self.bytes_written.saturating_add(other.bytes_written);
+        self.pages_erased = self.pages_erased.saturating_add(other.pages_erased);
+        self.total_accesses = self.total_accesses.saturating_add(other.total_accesses);
+        self.total_operations = self.total_operations.saturating_add(other.total_operations);
+        self.transactions_len = self.transactions_len.saturating_add(other.transactions_len);
+    }
+}
+
+impl AddAssign<&FlashSnapshot> for FlashSnapshot {
+    /// Same as [`FlashSnapshot::accumulate`].
+    fn add_assign(&mut self, other: &FlashSnapshot) {
+        self.accumulate(other);
+    }
 }
 
 impl<O: Clone + ToString, const RS: usize, const WS: usize, const ES: usize>
     SimulatedNorFlash<O, RS, WS, ES>
 {
+    /// Stream every recorded transaction to `writer` as one JSON object per line.
+    ///
+    /// Transactions are serialized with [`Transaction::to_json`] as soon as
+    /// they happen and are no longer kept in [`transactions`], so memory use
+    /// stays bounded for long runs. What gets recorded is still controlled by
+    /// the [`TransactionLogLevel`]. A previously set writer is flushed and
+    /// replaced.
+    ///
+    /// While streaming, everything built on the in-memory log only sees the
+    /// entries recorded before and after: [`undo_last`] can't step back
+    /// into streamed entries, [`transactions_since`] cursors never return
+    /// them, operations running meanwhile get empty
+    /// [`operation_transaction_ranges`] and the snapshot's
+    /// `transactions_len` doesn't count them. Failed writes are counted in
+    /// [`jsonl_write_errors`] and the transaction is dropped; streaming
+    /// continues with the next one.
+    ///
+    /// ```
+    /// use std::io::Write;
+    /// use std::sync::{Arc, Mutex};
+    ///
+    /// use embedded_storage::nor_flash::NorFlash;
+    /// use embedded_storage_sim::{SimulatedNorFlash, SimulatedNorFlashBuilder, TransactionLogLevel};
+    ///
+    /// #[derive(Clone, Default)]
+    /// struct Shared(Arc<Mutex<Vec<u8>>>);
+    ///
+    /// impl Write for Shared {
+    ///     fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+    ///         self.0.lock().unwrap().write(buf)
+    ///     }
+    ///     fn flush(&mut self) -> std::io::Result<()> {
+    ///         Ok(())
+    ///     }
+    /// }
+    ///
+    /// let mut flash: SimulatedNorFlash<&str> = SimulatedNorFlashBuilder::new(4096)
+    ///     .with_logging(TransactionLogLevel::Minimal)
+    ///     .build();
+    /// let output = Shared::default();
+    /// flash.set_jsonl_writer(Box::new(output.clone()));
+    /// flash.write(0, &[0; 4]).unwrap();
+    /// flash.clear_jsonl_writer().unwrap();
+    /// flash.write(4, &[0; 4]).unwrap();
+    ///
+    /// assert_eq!(output.0.lock().unwrap().split(|&b| b == b'\n').count(), 2);
+    /// assert_eq!(flash.transactions().len(), 1);
+    /// assert_eq!(flash.jsonl_write_errors(), 0);
+    /// ```
+    ///
+    /// [`transactions`]: Self::transactions
+    /// [`undo_last`]: Self::undo_last
+    /// [`transactions_since`]: Self::transactions_since
+    /// [`operation_transaction_ranges`]: Self::operation_transaction_ranges
+    /// [`jsonl_write_errors`]: SimulatedNorFlash::jsonl_write_errors
+    #[cfg(feature = "std")]
+    pub fn set_jsonl_writer(&mut self, writer: Box<dyn std::io::Write + Send>) {
+        self.flush_jsonl_writer().ok();
+        self.transaction_sink = Some(TransactionSink {
+            writer,
+            to_json: Transaction::to_json,
+        });
+    }
+    /// Register a callback that receives a snapshot without data every
+    /// `interval` operations.
+    ///
+    /// The snapshot is taken when the operation after every `interval`th
+    /// one is started via [`start_operation`](Self::start_operation), so it
+    /// includes all accesses of the completed operations and its
+    /// `total_operations` is a multiple of `interval`. Replaces any
+    /// previously registered callback.
+    ///
+    /// Panics if `interval` is zero.
+    ///
+    /// ```
+    /// use std::sync::mpsc;
+    ///
+    /// use embedded_storage::nor_flash::NorFlash;
+    /// use embedded_storage_sim::SimulatedNorFlash;
+    ///
+    /// let mut flash: SimulatedNorFlash<&str> = SimulatedNorFlash::new(4096);
+    /// let (tx, rx) = mpsc::channel();
+    /// flash.set_snapshot_interval(2, move |snapshot| tx.send(snapshot).unwrap());
+    /// for _ in 0..5 {
+    ///     flash.start_operation("store");
+    ///     flash.write(0, &[0]).unwrap();
+    /// }
+    /// let sampled: Vec<_> = rx.try_iter().map(|s| (s.total_operations, s.bytes_written)).collect();
+    /// assert_eq!(sampled, [(2, 2), (4, 4)]);
+    /// ```
+    pub fn set_snapshot_interval(
+        &mut self,
+        interval: usize,
+        callback: impl FnMut(FlashSnapshot) + Send + 'static,
+    ) {
+        assert!(interval > 0);
+        self.snapshot_sampler = Some(SnapshotSampler {
+            interval,
+            snapshot: |flash| flash.snapshot(false),
+            callback: Box::new(callback),
+        });
+    }
     /// Create a [`FlashSnapshot`]. When `with_data` is `true`, includes contents.
     pub fn snapshot(&self, with_data: bool) -> FlashSnapshot {
         FlashSnapshot {
-            data: if with_data {
+            data: if with_data && !self.metadata_only {
                 Some(self.data.clone())
             } else {
                 None
             },
+            content_hash: with_data.then(|| self.content_hash()),
+            capacity: self.size(),
+            erase_size: ES,
+            write_size: WS,
+            read_size: RS,
             page_cycles: self.page_erase_cycles().to_vec(),
             bytes_read: self.bytes_read(),
             bytes_written: self.bytes_written(),
@@ -496,6 +4029,24 @@ impl<O: Clone + ToString, const RS: usize, const WS: usize, const ES: usize>
             total_operations: self.total_operations(),
             transactions_len: self.transactions().len(),
             last_operation: self.current_operation.as_ref().map(|op| op.to_string()),
+            rng_seed: self.rng_seed(),
+            rng_draws: self.rng_draws(),
+            label: None,
+        }
+    }
+    /// Like [`snapshot`](Self::snapshot), with `label` attached.
+    ///
+    /// ```
+    /// use embedded_storage_sim::SimulatedNorFlash;
+    ///
+    /// let flash: SimulatedNorFlash<&str> = SimulatedNorFlash::new(4096);
+    /// let snapshot = flash.snapshot_labeled(false, "queue, no cache");
+    /// assert_eq!(snapshot.label.as_deref(), Some("queue, no cache"));
+    /// ```
+    pub fn snapshot_labeled(&self, with_data: bool, label: impl Into<String>) -> FlashSnapshot {
+        FlashSnapshot {
+            label: Some(label.into()),
+            ..self.snapshot(with_data)
         }
     }
 }
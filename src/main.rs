@@ -1,4 +1,4 @@
-use std::convert::Infallible;
+use embedded_storage_sim::FlashError;
 
 // use embedded_storage_sim::{FlashTimings, SimulatedNorFlashBuilder, TransactionLogLevel};
 // use fugit::{ExtU64, RateExtU64};
@@ -6,11 +6,11 @@ use std::convert::Infallible;
 mod gui;
 mod workloads;
 
-pub fn main() -> Result<(), sequential_storage::Error<Infallible>> {
+pub fn main() -> Result<(), sequential_storage::Error<FlashError>> {
     futures::executor::block_on(async_main())
 }
 
-async fn async_main() -> Result<(), sequential_storage::Error<Infallible>> {
+async fn async_main() -> Result<(), sequential_storage::Error<FlashError>> {
     let native_options = eframe::NativeOptions::default();
     eframe::run_native(
         "NOR Flash Simulator",
@@ -50,6 +50,7 @@ async fn async_main() -> Result<(), sequential_storage::Error<Infallible>> {
     //
     //     let timings = FlashTimings::new(
     //         embedded_storage_sim::SpiType::QSPI,
+    //         false,
     //         (125 / 2).MHz(),
     //         50.millis(),
     //         40,
@@ -0,0 +1,120 @@
+use crate::{AccessKind, FlashError, FlashStats, SimulatedNorFlash};
+
+/// A flash operation to be costed by [`SimulatedNorFlash::estimate`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PlannedOp {
+    /// Read `length` bytes at `offset`.
+    Read { offset: u32, length: usize },
+    /// Program `length` bytes at `offset` into already erased cells.
+    Write { offset: u32, length: usize },
+    /// Erase the pages in `from..to`.
+    Erase { from: u32, to: u32 },
+    /// Overwrite `length` bytes at `offset` via read-modify-write.
+    ///
+    /// Every page touched by the range is read back, erased with a single
+    /// command and programmed again in full, as needed when the target cells
+    /// are not erased.
+    Rewrite { offset: u32, length: usize },
+}
+
+impl<O: Clone, const RS: usize, const WS: usize, const ES: usize> SimulatedNorFlash<O, RS, WS, ES> {
+    /// Compute the statistics a sequence of operations would add, without
+    /// touching the contents or counters.
+    ///
+    /// Pass the result to [`FlashTimings::total_time`](crate::FlashTimings::total_time)
+    /// to estimate the worst-case duration before executing the sequence.
+    /// Zero-length operations are free, as when executed.
+    /// `total_operations` is always zero.
+    ///
+    /// Every operation is validated like the real access, so a sequence
+    /// with an out-of-bounds, misaligned or unmapped range returns the
+    /// error executing it would:
+    ///
+    /// ```
+    /// use embedded_storage_sim::{FlashError, PlannedOp, SimulatedNorFlash};
+    ///
+    /// let flash: SimulatedNorFlash = SimulatedNorFlash::new(2 * 4096);
+    /// let stats = flash
+    ///     .estimate(&[
+    ///         PlannedOp::Erase { from: 0, to: 4096 },
+    ///         PlannedOp::Rewrite { offset: 4000, length: 200 },
+    ///     ])
+    ///     .unwrap();
+    /// assert_eq!((stats.pages_erased, stats.bytes_written), (3, 2 * 4096));
+    ///
+    /// let backwards = PlannedOp::Erase { from: 4096, to: 0 };
+    /// assert_eq!(flash.estimate(&[backwards]).unwrap_err(), FlashError::OutOfBounds { offset: 4096 });
+    /// let unaligned = PlannedOp::Erase { from: 0, to: 100 };
+    /// assert_eq!(flash.estimate(&[unaligned]).unwrap_err(), FlashError::NotAligned { offset: 100 });
+    /// let past_end = PlannedOp::Rewrite { offset: u32::MAX, length: 2 };
+    /// assert_eq!(flash.estimate(&[past_end]).unwrap_err(), FlashError::OutOfBounds { offset: u32::MAX });
+    /// ```
+    pub fn estimate(&self, ops: &[PlannedOp]) -> Result<FlashStats, FlashError> {
+        let mut stats = FlashStats::default();
+        for op in ops {
+            match *op {
+                PlannedOp::Read { offset, length } => {
+                    let end = offset.saturating_add(length as u32);
+                    self.check_access(offset..end, AccessKind::Read)?;
+                    add_read(&mut stats, length, self.accesses(offset, length))
+                }
+                PlannedOp::Write { offset, length } => {
+                    let end = offset.saturating_add(length as u32);
+                    self.check_access(offset..end, AccessKind::Write)?;
+                    add_write(
+                        &mut stats,
+                        length,
+                        self.program_units(offset, length),
+                        self.accesses(offset, length),
+                    )
+                }
+                PlannedOp::Erase { from, to } => {
+                    self.check_access(from..to, AccessKind::Erase)?;
+                    add_erase(&mut stats, (to - from) as usize / ES)
+                }
+                PlannedOp::Rewrite { offset, length } => {
+                    let end = offset.saturating_add(length as u32);
+                    self.check_access_aligned(offset..end, 1)?;
+                    if length == 0 {
+                        continue;
+                    }
+                    let from = Self::align_down_erase(offset);
+                    let to = Self::align_up_erase(end)?;
+                    self.check_access(from..to, AccessKind::Erase)?;
+                    let length = (to - from) as usize;
+                    add_read(&mut stats, length, self.accesses(from, length));
+                    add_erase(&mut stats, length / ES);
+                    add_write(
+                        &mut stats,
+                        length,
+                        self.program_units(from, length),
+                        self.accesses(from, length),
+                    );
+                }
+            }
+        }
+        Ok(stats)
+    }
+}
+
+fn add_read(stats: &mut FlashStats, length: usize, accesses: usize) {
+    if length != 0 {
+        stats.bytes_read += length;
+        stats.read_accesses += accesses;
+    }
+}
+
+fn add_write(stats: &mut FlashStats, length: usize, program_units: usize, accesses: usize) {
+    if length != 0 {
+        stats.bytes_written += length;
+        stats.program_units_written += program_units;
+        stats.write_accesses += accesses;
+    }
+}
+
+fn add_erase(stats: &mut FlashStats, pages: usize) {
+    if pages != 0 {
+        stats.pages_erased += pages;
+        stats.erase_accesses += 1;
+    }
+}
@@ -0,0 +1,82 @@
+use alloc::vec;
+use std::io::{self, Read, Seek, SeekFrom};
+
+use embedded_storage::nor_flash::ReadNorFlash;
+
+use crate::SimulatedNorFlash;
+
+/// [`Read`] + [`Seek`] adapter over the flash contents.
+///
+/// Created by [`SimulatedNorFlash::as_reader`].
+pub struct FlashReader<'a, O, const RS: usize, const WS: usize, const ES: usize> {
+    flash: &'a mut SimulatedNorFlash<O, RS, WS, ES>,
+    position: u64,
+}
+
+impl<O: Clone, const RS: usize, const WS: usize, const ES: usize> SimulatedNorFlash<O, RS, WS, ES> {
+    /// Borrow the flash as a [`std::io::Read`] + [`Seek`] stream, starting at offset 0.
+    ///
+    /// Every `read` goes through [`ReadNorFlash::read`], so stuck bits are
+    /// applied and stats and the transaction log are updated as usual. Reads
+    /// at unaligned positions fetch the covering `READ_SIZE` aligned range,
+    /// which is what gets counted.
+    ///
+    /// ```
+    /// use std::io::{Read, Seek, SeekFrom};
+    ///
+    /// use embedded_storage::nor_flash::NorFlash;
+    /// use embedded_storage_sim::SimulatedNorFlash;
+    ///
+    /// let mut flash: SimulatedNorFlash = SimulatedNorFlash::new(4096);
+    /// flash.write(100, b"header").unwrap();
+    ///
+    /// let mut reader = flash.as_reader();
+    /// reader.seek(SeekFrom::Start(100)).unwrap();
+    /// let mut header = [0; 6];
+    /// reader.read_exact(&mut header).unwrap();
+    /// assert_eq!(&header, b"header");
+    /// assert_eq!(flash.bytes_read(), 6);
+    /// ```
+    pub fn as_reader(&mut self) -> FlashReader<'_, O, RS, WS, ES> {
+        FlashReader {
+            flash: self,
+            position: 0,
+        }
+    }
+}
+
+impl<O: Clone, const RS: usize, const WS: usize, const ES: usize> Read
+    for FlashReader<'_, O, RS, WS, ES>
+{
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let size = self.flash.size() as u64;
+        if self.position >= size || buf.is_empty() {
+            return Ok(0);
+        }
+        let len = buf.len().min((size - self.position) as usize);
+        let start = self.position as usize / RS * RS;
+        let end = (self.position as usize + len).div_ceil(RS) * RS;
+        let mut aligned = vec![0; end - start];
+        ReadNorFlash::read(self.flash, start as u32, &mut aligned).map_err(io::Error::other)?;
+        let skip = self.position as usize - start;
+        buf[..len].copy_from_slice(&aligned[skip..skip + len]);
+        self.position += len as u64;
+        Ok(len)
+    }
+}
+
+impl<O: Clone, const RS: usize, const WS: usize, const ES: usize> Seek
+    for FlashReader<'_, O, RS, WS, ES>
+{
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        let position = match pos {
+            SeekFrom::Start(offset) => Some(offset),
+            SeekFrom::End(delta) => (self.flash.size() as u64).checked_add_signed(delta),
+            SeekFrom::Current(delta) => self.position.checked_add_signed(delta),
+        };
+        self.position = position.ok_or_else(|| {
+            io::Error::new(io::ErrorKind::InvalidInput, "seek before start of flash")
+        })?;
+        Ok(self.position)
+    }
+}
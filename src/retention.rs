@@ -0,0 +1,133 @@
+use fugit::SecsDurationU64;
+use rand::Rng;
+
+use crate::{SimulatedNorFlash, Transaction, TransactionLogLevel};
+
+/// Boltzmann constant in eV/K.
+const BOLTZMANN: f64 = 8.617_333_262e-5;
+
+/// Data retention model: programmed (`0`) bits slowly relax to `1`.
+///
+/// Every programmed bit flips independently, with exponential decay of the
+/// stored charge. At `reference_temperature` half of the programmed bits are
+/// lost after `half_life`; at other temperatures the half-life is scaled by
+/// the Arrhenius acceleration factor
+/// `exp(activation_energy / k * (1 / reference_temperature - 1 / temperature))`.
+/// Temperatures are in Kelvin and the activation energy is in eV.
+///
+/// Set with [`with_retention`](crate::SimulatedNorFlashBuilder::with_retention)
+/// and apply with [`SimulatedNorFlash::advance_time`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RetentionModel {
+    half_life: SecsDurationU64,
+    reference_temperature: f32,
+    activation_energy: f32,
+}
+
+impl RetentionModel {
+    /// `half_life` at `reference_temperature` (K), with `activation_energy` (eV).
+    ///
+    /// Panics if the half-life or the temperature is zero.
+    pub fn new(
+        half_life: SecsDurationU64,
+        reference_temperature: f32,
+        activation_energy: f32,
+    ) -> Self {
+        assert!(half_life.ticks() > 0 && reference_temperature > 0.0);
+        Self {
+            half_life,
+            reference_temperature,
+            activation_energy,
+        }
+    }
+    /// How much faster charge is lost at `temperature` (K) than at the
+    /// reference temperature.
+    pub fn acceleration_factor(&self, temperature: f32) -> f64 {
+        let inverse_difference = 1.0 / self.reference_temperature as f64 - 1.0 / temperature as f64;
+        (self.activation_energy as f64 / BOLTZMANN * inverse_difference).exp()
+    }
+    /// Effective half-life at `temperature` (K), rounded to whole seconds.
+    pub fn half_life_at(&self, temperature: f32) -> SecsDurationU64 {
+        let seconds = self.half_life.ticks() as f64 / self.acceleration_factor(temperature);
+        SecsDurationU64::from_ticks((seconds + 0.5) as u64)
+    }
+    /// Probability that a programmed bit flips within `elapsed` at `temperature` (K).
+    pub fn flip_probability(&self, elapsed: SecsDurationU64, temperature: f32) -> f64 {
+        let half_lives = elapsed.ticks() as f64 * self.acceleration_factor(temperature)
+            / self.half_life.ticks() as f64;
+        1.0 - (-half_lives).exp2()
+    }
+}
+
+impl<O: Clone, const RS: usize, const WS: usize, const ES: usize> SimulatedNorFlash<O, RS, WS, ES> {
+    /// Let `elapsed` pass at an ambient `temperature` (K) and return the
+    /// number of bits that relaxed.
+    ///
+    /// Each programmed bit flips to `1` with the probability given by
+    /// [`RetentionModel::flip_probability`], drawn from the failure
+    /// injection RNG. Like [`corrupt_bits`](Self::corrupt_bits) the flips are
+    /// recorded as a [`Transaction::Corruption`] covering the affected bytes
+    /// unless logging is disabled. Simulate a bake test with a high
+    /// temperature:
+    ///
+    /// ```
+    /// use embedded_storage::nor_flash::{NorFlash, ReadNorFlash};
+    /// use embedded_storage_sim::{RetentionModel, SimulatedNorFlash, SimulatedNorFlashBuilder};
+    /// use fugit::SecsDurationU64;
+    ///
+    /// // ten years at 55 °C, Ea = 0.6 eV
+    /// let model = RetentionModel::new(SecsDurationU64::secs(315_360_000), 328.15, 0.6);
+    /// assert!(model.half_life_at(398.15) < SecsDurationU64::secs(10_000_000));
+    ///
+    /// let mut flash: SimulatedNorFlash = SimulatedNorFlashBuilder::new(4096)
+    ///     .with_retention(model)
+    ///     .with_rng_seed(1)
+    ///     .build();
+    /// flash.write(0, &[0x00; 16]).unwrap();
+    /// assert_eq!(flash.advance_time(SecsDurationU64::secs(1), 328.15), 0);
+    /// // a thousand years at 125 °C loses everything
+    /// assert_eq!(flash.advance_time(SecsDurationU64::secs(31_536_000_000), 398.15), 128);
+    /// let mut data = [0; 16];
+    /// flash.read(0, &mut data).unwrap();
+    /// assert_eq!(data, [0xff; 16]);
+    /// ```
+    ///
+    /// Does nothing with RAM semantics or without stored contents. Panics
+    /// if no retention model is set.
+    pub fn advance_time(&mut self, elapsed: SecsDurationU64, temperature: f32) -> usize {
+        let model = self.retention.expect("no retention model configured");
+        if self.ram_semantics || self.metadata_only {
+            return 0;
+        }
+        let probability = model.flip_probability(elapsed, temperature);
+        let mut flipped = 0;
+        let mut first = None;
+        let mut last = 0;
+        for (offset, byte) in self.data.iter_mut().enumerate() {
+            for bit in 0..8 {
+                let mask = 1 << bit;
+                if *byte & mask == 0 && self.rng.random::<f64>() < probability {
+                    *byte |= mask;
+                    flipped += 1;
+                    first.get_or_insert(offset);
+                    last = offset;
+                }
+            }
+        }
+        if let Some(first) = first {
+            self.update_sector_crcs(first..last + 1);
+            self.invalidate_read_cache(first..last + 1);
+        }
+        if let Some(first) = first
+            && self.log_level != TransactionLogLevel::None
+        {
+            self.record(Transaction::corruption(
+                first as u32,
+                last as u32 + 1,
+                flipped,
+                self.current_operation.clone(),
+            ));
+        }
+        flipped
+    }
+}
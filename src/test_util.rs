@@ -0,0 +1,36 @@
+//! Helpers for writing async storage tests against the simulator.
+
+use core::{
+    pin::pin,
+    task::{Context, Poll, Waker},
+};
+
+/// Run `future` to completion on the current thread.
+///
+/// This is a minimal, deterministic executor: the future is polled in a
+/// loop with a no-op waker until it is ready, without pulling in an async
+/// runtime. The simulator's own operations complete on their first poll, so
+/// a typical storage test never spins.
+///
+/// ```
+/// use embedded_storage_async::nor_flash::{NorFlash, ReadNorFlash};
+/// use embedded_storage_sim::{SimulatedNorFlash, test_util};
+///
+/// let mut flash: SimulatedNorFlash = SimulatedNorFlash::new(4096);
+/// let mut buf = [0; 4];
+/// test_util::run(async {
+///     flash.write(0, b"test").await.unwrap();
+///     flash.read(0, &mut buf).await.unwrap();
+/// });
+/// assert_eq!(&buf, b"test");
+/// ```
+pub fn run<F: Future>(future: F) -> F::Output {
+    let mut future = pin!(future);
+    let mut cx = Context::from_waker(Waker::noop());
+    loop {
+        if let Poll::Ready(output) = future.as_mut().poll(&mut cx) {
+            return output;
+        }
+        core::hint::spin_loop();
+    }
+}
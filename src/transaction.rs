@@ -1,4 +1,10 @@
-use std::fmt::Debug;
+use alloc::{
+    string::{String, ToString},
+    vec::Vec,
+};
+use core::fmt::{self, Debug, Display, Write as _};
+
+use crate::FlashError;
 
 /// Controls how much information is recorded per storage operation.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -11,10 +17,55 @@ pub enum TransactionLogLevel {
     WriteDataOnly,
     /// Log data for read and write transactions
     ReadWriteData,
+    /// Additionally log the contents before and after each write => writes
+    /// are invertible
+    WriteBeforeAfter,
     /// Log all transaction data including data before erase transactions
     Full,
 }
 
+/// Kind of storage access, e.g. of a [`Transaction::Rejected`] attempt.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AccessKind {
+    Read,
+    Write,
+    Erase,
+}
+
+impl Display for AccessKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            AccessKind::Read => "read",
+            AccessKind::Write => "write",
+            AccessKind::Erase => "erase",
+        })
+    }
+}
+
+/// Variant of a [`Transaction`], without its payload.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TransactionKind {
+    Read,
+    Write,
+    Erase,
+    Corruption,
+    Marker,
+    Rejected,
+}
+
+impl Display for TransactionKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            TransactionKind::Read => "read",
+            TransactionKind::Write => "write",
+            TransactionKind::Erase => "erase",
+            TransactionKind::Corruption => "corruption",
+            TransactionKind::Marker => "marker",
+            TransactionKind::Rejected => "rejected",
+        })
+    }
+}
+
 /// A recorded storage operation emitted by the simulator.
 ///
 /// Each variant may carry optional data depending on the active
@@ -32,7 +83,9 @@ pub enum Transaction<O = ()> {
     Write {
         operation: Option<O>,
         offset: u32,
+        length: usize,
         data: Option<Vec<u8>>,
+        before_write: Option<Vec<u8>>,
         after_write: Option<Vec<u8>>,
     },
     Erase {
@@ -41,11 +94,54 @@ pub enum Transaction<O = ()> {
         to: u32,
         data: Option<Vec<u8>>,
     },
+    /// One-time bit flips injected by
+    /// [`SimulatedNorFlash::corrupt_bits`](crate::SimulatedNorFlash::corrupt_bits).
+    Corruption {
+        operation: Option<O>,
+        from: u32,
+        to: u32,
+        count: usize,
+    },
+    /// User-defined marker, e.g. to delimit logical commits. See
+    /// [`SimulatedNorFlash::mark`](crate::SimulatedNorFlash::mark).
+    Marker { operation: Option<O>, label: String },
+    /// An access that failed with `reason` and left the flash untouched.
+    ///
+    /// Recorded at every log level except [`TransactionLogLevel::None`], so
+    /// the log covers everything the caller attempted.
+    Rejected {
+        operation: Option<O>,
+        kind: AccessKind,
+        offset: u32,
+        reason: FlashError,
+    },
 }
 impl<O> Transaction<O> {
+    /// Which variant this transaction is.
+    pub fn kind(&self) -> TransactionKind {
+        match self {
+            Transaction::Read { .. } => TransactionKind::Read,
+            Transaction::Write { .. } => TransactionKind::Write,
+            Transaction::Erase { .. } => TransactionKind::Erase,
+            Transaction::Corruption { .. } => TransactionKind::Corruption,
+            Transaction::Marker { .. } => TransactionKind::Marker,
+            Transaction::Rejected { .. } => TransactionKind::Rejected,
+        }
+    }
+    /// Operation tag that was active when the transaction was recorded.
+    pub fn operation(&self) -> Option<&O> {
+        match self {
+            Transaction::Read { operation, .. }
+            | Transaction::Write { operation, .. }
+            | Transaction::Erase { operation, .. }
+            | Transaction::Corruption { operation, .. }
+            | Transaction::Marker { operation, .. }
+            | Transaction::Rejected { operation, .. } => operation.as_ref(),
+        }
+    }
     /// Construct a `Read` transaction based on the configured log level.
     ///
-    /// When `level` is `ReadWriteData` or `Full`, the `data` buffer is captured.
+    /// When `level` is `ReadWriteData` or above, the `data` buffer is captured.
     pub fn read(
         level: TransactionLogLevel,
         offset: u32,
@@ -54,7 +150,9 @@ impl<O> Transaction<O> {
         operation: Option<O>,
     ) -> Self {
         let data = match level {
-            TransactionLogLevel::ReadWriteData | TransactionLogLevel::Full => Some(data.to_vec()),
+            TransactionLogLevel::ReadWriteData
+            | TransactionLogLevel::WriteBeforeAfter
+            | TransactionLogLevel::Full => Some(data.to_vec()),
             _ => None,
         };
         Transaction::Read {
@@ -66,29 +164,37 @@ impl<O> Transaction<O> {
     }
     /// Construct a `Write` transaction based on the configured log level.
     ///
-    /// The data being written is captured for `WriteDataOnly`, `ReadWriteData`
-    /// and `Full`. The resulting post-write contents (`after_write`) are only
-    /// captured for `Full`.
+    /// The data being written is captured for `WriteDataOnly` and above. The
+    /// stored contents before the write (`before_write`) and the resulting
+    /// post-write contents (`after_write`) are only captured for
+    /// `WriteBeforeAfter` and `Full`.
     pub fn write(
         level: TransactionLogLevel,
         offset: u32,
         data: &[u8],
+        before_write: &[u8],
         after_write: &[u8],
         operation: Option<O>,
     ) -> Self {
+        let length = data.len();
         let data = match level {
             TransactionLogLevel::WriteDataOnly
             | TransactionLogLevel::ReadWriteData
+            | TransactionLogLevel::WriteBeforeAfter
             | TransactionLogLevel::Full => Some(data.to_vec()),
             _ => None,
         };
-        let after_write = match level {
-            TransactionLogLevel::Full => Some(after_write.to_vec()),
-            _ => None,
+        let (before_write, after_write) = match level {
+            TransactionLogLevel::WriteBeforeAfter | TransactionLogLevel::Full => {
+                (Some(before_write.to_vec()), Some(after_write.to_vec()))
+            }
+            _ => (None, None),
         };
         Transaction::Write {
             offset,
+            length,
             data,
+            before_write,
             after_write,
             operation,
         }
@@ -114,4 +220,219 @@ impl<O> Transaction<O> {
             operation,
         }
     }
+    /// Construct a `Corruption` transaction for `count` bits flipped in `from..to`.
+    pub fn corruption(from: u32, to: u32, count: usize, operation: Option<O>) -> Self {
+        Transaction::Corruption {
+            operation,
+            from,
+            to,
+            count,
+        }
+    }
+    /// Construct a `Marker` transaction with the given label.
+    pub fn marker(label: &str, operation: Option<O>) -> Self {
+        Transaction::Marker {
+            operation,
+            label: label.to_string(),
+        }
+    }
+    /// Construct a `Rejected` transaction for a `kind` access at `offset`.
+    pub fn rejected(
+        kind: AccessKind,
+        offset: u32,
+        reason: FlashError,
+        operation: Option<O>,
+    ) -> Self {
+        Transaction::Rejected {
+            operation,
+            kind,
+            offset,
+            reason,
+        }
+    }
+}
+
+impl<O: ToString> Transaction<O> {
+    /// One-line human-readable description, same as the [`Display`] output.
+    pub fn summary(&self) -> String {
+        self.to_string()
+    }
+    /// Serialize as a single-line JSON object.
+    ///
+    /// The object always has a `kind` and an `operation` (the tag as a
+    /// string, or `null`), plus the variant's offsets and lengths. Captured
+    /// buffers are included as full hex strings, and omitted when they were
+    /// not captured at the active [`TransactionLogLevel`].
+    pub fn to_json(&self) -> String {
+        let mut json = String::new();
+        self.write_json(&mut json)
+            .expect("writing to a String cannot fail");
+        json
+    }
+    fn write_json(&self, w: &mut String) -> fmt::Result {
+        write!(w, "{{\"kind\":\"{}\",\"operation\":", self.kind())?;
+        match self.operation() {
+            Some(operation) => write_json_str(w, &operation.to_string())?,
+            None => w.push_str("null"),
+        }
+        match self {
+            Transaction::Read {
+                offset,
+                length,
+                data,
+                ..
+            } => {
+                write!(w, ",\"offset\":{},\"length\":{}", offset, length)?;
+                write_json_hex(w, "data", data)?;
+            }
+            Transaction::Write {
+                offset,
+                length,
+                data,
+                before_write,
+                after_write,
+                ..
+            } => {
+                write!(w, ",\"offset\":{},\"length\":{}", offset, length)?;
+                write_json_hex(w, "data", data)?;
+                write_json_hex(w, "before_write", before_write)?;
+                write_json_hex(w, "after_write", after_write)?;
+            }
+            Transaction::Erase { from, to, data, .. } => {
+                write!(w, ",\"from\":{},\"to\":{}", from, to)?;
+                write_json_hex(w, "data", data)?;
+            }
+            Transaction::Corruption {
+                from, to, count, ..
+            } => write!(w, ",\"from\":{},\"to\":{},\"count\":{}", from, to, count)?,
+            Transaction::Marker { label, .. } => {
+                w.push_str(",\"label\":");
+                write_json_str(w, label)?;
+            }
+            Transaction::Rejected {
+                kind,
+                offset,
+                reason,
+                ..
+            } => {
+                write!(
+                    w,
+                    ",\"access\":\"{}\",\"offset\":{},\"reason\":",
+                    kind, offset
+                )?;
+                write_json_str(w, &reason.to_string())?;
+            }
+        }
+        w.push('}');
+        Ok(())
+    }
+}
+
+/// Write `value` as a quoted and escaped JSON string.
+fn write_json_str(w: &mut String, value: &str) -> fmt::Result {
+    w.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => w.push_str("\\\""),
+            '\\' => w.push_str("\\\\"),
+            '\n' => w.push_str("\\n"),
+            '\r' => w.push_str("\\r"),
+            '\t' => w.push_str("\\t"),
+            c if c.is_control() => write!(w, "\\u{:04x}", c as u32)?,
+            c => w.push(c),
+        }
+    }
+    w.push('"');
+    Ok(())
+}
+
+/// Write `,"key":"<hex>"` if `data` was captured.
+fn write_json_hex(w: &mut String, key: &str, data: &Option<Vec<u8>>) -> fmt::Result {
+    if let Some(data) = data {
+        write!(w, ",\"{}\":\"", key)?;
+        for byte in data {
+            write!(w, "{:02x}", byte)?;
+        }
+        w.push('"');
+    }
+    Ok(())
+}
+
+/// Compact one-line form, e.g. `WRITE @0x1000 len=16 op=Store data=a5a5...`.
+///
+/// Captured buffers are hex-encoded and truncated after
+/// [`HEX_DUMP_LIMIT`] bytes. Fields that were not captured at the active
+/// [`TransactionLogLevel`] are omitted.
+impl<O: ToString> Display for Transaction<O> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Transaction::Read { offset, length, .. } => {
+                write!(f, "READ @{:#x} len={}", offset, length)?
+            }
+            Transaction::Write { offset, length, .. } => {
+                write!(f, "WRITE @{:#x} len={}", offset, length)?
+            }
+            Transaction::Erase { from, to, .. } => {
+                write!(f, "ERASE @{:#x} len={}", from, to - from)?
+            }
+            Transaction::Corruption {
+                from, to, count, ..
+            } => write!(f, "CORRUPT @{:#x} len={} bits={}", from, to - from, count)?,
+            Transaction::Marker { label, .. } => write!(f, "MARK {}", label)?,
+            Transaction::Rejected {
+                kind,
+                offset,
+                reason,
+                ..
+            } => write!(f, "REJECT {} @{:#x} ({})", kind, offset, reason)?,
+        }
+        if let Some(operation) = self.operation() {
+            write!(f, " op={}", operation.to_string())?;
+        }
+        match self {
+            Transaction::Read { data, .. } | Transaction::Erase { data, .. } => {
+                if let Some(data) = data {
+                    write!(f, " data={}", HexDump(data))?;
+                }
+            }
+            Transaction::Write {
+                data,
+                before_write,
+                after_write,
+                ..
+            } => {
+                if let Some(data) = data {
+                    write!(f, " data={}", HexDump(data))?;
+                }
+                if let Some(before_write) = before_write {
+                    write!(f, " before={}", HexDump(before_write))?;
+                }
+                if let Some(after_write) = after_write {
+                    write!(f, " after={}", HexDump(after_write))?;
+                }
+            }
+            Transaction::Corruption { .. }
+            | Transaction::Marker { .. }
+            | Transaction::Rejected { .. } => {}
+        }
+        Ok(())
+    }
+}
+
+/// Maximum number of bytes shown by the [`Display`] impl of [`Transaction`].
+pub const HEX_DUMP_LIMIT: usize = 16;
+
+/// Hex encoder that truncates after [`HEX_DUMP_LIMIT`] bytes.
+struct HexDump<'a>(&'a [u8]);
+
+impl Display for HexDump<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for byte in self.0.iter().take(HEX_DUMP_LIMIT) {
+            write!(f, "{:02x}", byte)?;
+        }
+        if self.0.len() > HEX_DUMP_LIMIT {
+            write!(f, "...({} bytes)", self.0.len())?;
+        }
+        Ok(())
+    }
 }
@@ -1,6 +1,6 @@
 pub mod sequential_storage;
 
-#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
 pub enum Operation {
     QueuePush,
     QueuePop,
@@ -115,7 +115,7 @@ where
         eprintln!("Map filled with {} items", count);
 
         // benchmark overwriting existing keys sequentially
-        map.flash().reset_stats();
+        map.flash().reset_counters();
         let mut op_stats = Vec::new();
         for key in 0..count {
             map.flash().start_operation(Operation::MapStore);
@@ -130,7 +130,7 @@ where
         let sequential_overwrite_ops = op_stats;
 
         // benchmark overwriting existing keys randomly
-        map.flash().reset_stats();
+        map.flash().reset_counters();
         let mut op_stats = Vec::new();
         for _ in 0..random_count {
             let key = rng.random_range(0..count);